@@ -1,31 +0,0 @@
-CREATE TABLE IF NOT EXISTS users (
-  id          SERIAL PRIMARY KEY,
-  username    VARCHAR(64) NOT NULL UNIQUE,
-  password    VARCHAR(128) NOT NULL,
-  deleted_at  TIMESTAMPTZ DEFAULT NULL,
-  token       TEXT DEFAULT NULL
-);
-
-CREATE TABLE IF NOT EXISTS tickets (
-  id            SERIAL PRIMARY KEY,
-  priority      VARCHAR(4) DEFAULT NULL,
-  title         VARCHAR(255) NOT NULL,
-  completed_at  TIMESTAMPTZ DEFAULT NULL,
-  description   TEXT DEFAULT NULL,
-  deleted_at    TIMESTAMPTZ DEFAULT NULL,
-  user_id       INTEGER DEFAULT NULL, 
-  in_progress   BOOLEAN DEFAULT FALSE,
-  CONSTRAINT fk_users FOREIGN KEY (user_id) REFERENCES users(id)
-);
-
-INSERT INTO users (username, password) VALUES ('deleteduser', '$2b$12$x3hs5oMgjHdcV1GUEElfsO19JtS6.ixJAX9Cj62GyhpdPAIW25sky');
-
-INSERT INTO tickets (title, deleted_at, user_id) VALUES (
-  'my deleted ticket',
-  NOW(),
-  (select id from users where username = 'deleteduser')
-);
-
-INSERT INTO tickets (priority, title, description, in_progress) VALUES 
-  ('A', 'I am a ticket, you can complete me by checking the box', 'This is my description', true),
-  ('B', 'See my details for by clicking me', 'My description can be changed', true);
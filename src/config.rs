@@ -0,0 +1,681 @@
+use std::{collections::HashMap, env, fmt, net::SocketAddr, path::PathBuf, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use sea_orm::ActiveEnum;
+use serde::Deserialize;
+
+use crate::database::sea_orm_active_enums::TicketStatus;
+
+/// Path of the optional TOML file, overridden with `TICKETSTORE_CONFIG`
+const DEFAULT_CONFIG_PATH: &str = "ticketstore.toml";
+
+/// Application settings, read once at startup
+///
+/// Values come from the TOML file first, then environment variables override
+/// them, see `Config::load`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub jwt: JwtConfig,
+    pub cors: CorsConfig,
+    pub log: LogConfig,
+    pub workflow: WorkflowConfig,
+    pub tickets: TicketsConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind_address: SocketAddr,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// Apply pending migrations before serving
+    pub auto_migrate: bool,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JwtConfig {
+    /// Shorthand for a single HS256 key, ignored when `keys` is set
+    pub secret: String,
+    /// Key new tokens are signed with, defaults to the only key when there is one
+    pub signing_kid: Option<String>,
+    pub keys: Vec<JwtKeyConfig>,
+    /// Lifetime of access tokens
+    pub expiry_minutes: i64,
+    /// Lifetime of a session, extended every time its refresh token rotates
+    pub refresh_expiry_days: i64,
+    pub issuer: String,
+    pub audience: String,
+    /// Look the session up on every request so logout and revocation take
+    /// effect immediately, otherwise access tokens stay valid until they expire
+    pub check_revocation: bool,
+}
+
+/// One signing or verification key, identified in token headers by `kid`
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JwtKeyConfig {
+    pub kid: String,
+    pub algorithm: JwtAlgorithm,
+    /// HS256 only
+    pub secret: Option<String>,
+    /// PEM file, only needed for the key named by `signing_kid`
+    pub private_key_path: Option<PathBuf>,
+    /// PEM file, required for RS256 and EdDSA keys
+    pub public_key_path: Option<PathBuf>,
+    /// Retired keys keep verifying (and stay published) until this instant
+    pub verify_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum JwtAlgorithm {
+    HS256,
+    RS256,
+    EdDSA,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// `*` allows any origin
+    pub allowed_origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub format: LogFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    Json,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkflowConfig {
+    /// Statuses a ticket may move to from each status, a status missing
+    /// here has no way out
+    pub transitions: HashMap<TicketStatus, Vec<TicketStatus>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TicketsConfig {
+    /// Reject ticket writes without an `If-Match` header with 428, so clients
+    /// cannot overwrite changes they have not seen
+    pub require_if_match: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: SocketAddr::from(([0, 0, 0, 0], 3000)),
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            max_connections: 10,
+            min_connections: 1,
+            auto_migrate: false,
+        }
+    }
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        Self {
+            secret: String::new(),
+            signing_kid: None,
+            keys: Vec::new(),
+            expiry_minutes: 15,
+            refresh_expiry_days: 30,
+            issuer: "axum-ticketstore".to_owned(),
+            audience: "axum-ticketstore".to_owned(),
+            check_revocation: true,
+        }
+    }
+}
+
+// Keep the secret out of logs
+impl fmt::Debug for JwtConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtConfig")
+            .field("secret", &"<redacted>")
+            .field("signing_kid", &self.signing_kid)
+            .field("keys", &self.keys)
+            .field("expiry_minutes", &self.expiry_minutes)
+            .field("refresh_expiry_days", &self.refresh_expiry_days)
+            .field("issuer", &self.issuer)
+            .field("audience", &self.audience)
+            .field("check_revocation", &self.check_revocation)
+            .finish()
+    }
+}
+
+impl fmt::Debug for JwtKeyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtKeyConfig")
+            .field("kid", &self.kid)
+            .field("algorithm", &self.algorithm)
+            .field("secret", &self.secret.as_ref().map(|_| "<redacted>"))
+            .field("private_key_path", &self.private_key_path)
+            .field("public_key_path", &self.public_key_path)
+            .field("verify_until", &self.verify_until)
+            .finish()
+    }
+}
+
+impl JwtConfig {
+    /// Configured keys, or a single HS256 key built from `secret`
+    pub fn key_configs(&self) -> Vec<JwtKeyConfig> {
+        if !self.keys.is_empty() || self.secret.is_empty() {
+            return self.keys.clone();
+        }
+
+        vec![JwtKeyConfig {
+            kid: "default".to_owned(),
+            algorithm: JwtAlgorithm::HS256,
+            secret: Some(self.secret.clone()),
+            private_key_path: None,
+            public_key_path: None,
+            verify_until: None,
+        }]
+    }
+
+    pub fn signing_kid(&self) -> Option<String> {
+        match (&self.signing_kid, self.key_configs().as_slice()) {
+            (Some(kid), _) => Some(kid.clone()),
+            (None, [only_key]) => Some(only_key.kid.clone()),
+            (None, _) => None,
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["*".to_owned()],
+        }
+    }
+}
+
+impl Default for WorkflowConfig {
+    fn default() -> Self {
+        use TicketStatus::*;
+
+        Self {
+            transitions: HashMap::from([
+                (Open, vec![InProgress, Blocked, Done, Cancelled]),
+                (InProgress, vec![Open, Blocked, Done, Cancelled]),
+                (Blocked, vec![Open, InProgress, Cancelled]),
+                (Done, vec![Open]),
+                (Cancelled, vec![Open]),
+            ]),
+        }
+    }
+}
+
+impl WorkflowConfig {
+    pub fn allows(&self, from: TicketStatus, to: TicketStatus) -> bool {
+        self.transitions
+            .get(&from)
+            .is_some_and(|targets| targets.contains(&to))
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err("expected `pretty` or `json`".to_owned()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    Env {
+        name: &'static str,
+        reason: String,
+    },
+    Invalid {
+        field: &'static str,
+        reason: String,
+    },
+    Key {
+        kid: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, source } => {
+                write!(f, "cannot read {}: {source}", path.display())
+            }
+            ConfigError::Parse { path, source } => {
+                write!(f, "cannot parse {}: {source}", path.display())
+            }
+            ConfigError::Env { name, reason } => write!(f, "invalid ${name}: {reason}"),
+            ConfigError::Invalid { field, reason } => write!(f, "invalid {field}: {reason}"),
+            ConfigError::Key { kid, reason } => write!(f, "invalid jwt key {kid:?}: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Reads the TOML file (if any), applies environment overrides and validates
+    ///
+    /// The file is `$TICKETSTORE_CONFIG`, falling back to `ticketstore.toml` in
+    /// the working directory when it exists
+    pub fn load() -> Result<Self, ConfigError> {
+        let config = Self::read()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Like `load`, but only validates the database settings, the rest is
+    /// not needed to run migrations
+    pub fn load_for_migrate() -> Result<Self, ConfigError> {
+        let config = Self::read()?;
+        config.validate_database()?;
+        Ok(config)
+    }
+
+    fn read() -> Result<Self, ConfigError> {
+        let mut config = match env::var_os("TICKETSTORE_CONFIG") {
+            Some(path) => Self::from_file(PathBuf::from(path))?,
+            None if PathBuf::from(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(PathBuf::from(DEFAULT_CONFIG_PATH))?
+            }
+            None => Self::default(),
+        };
+
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    fn from_file(path: PathBuf) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(&path).map_err(|source| ConfigError::Read {
+            path: path.clone(),
+            source,
+        })?;
+
+        toml::from_str(&contents).map_err(|source| ConfigError::Parse { path, source })
+    }
+
+    fn apply_env(&mut self) -> Result<(), ConfigError> {
+        if let Some(bind_address) = parse_env("BIND_ADDRESS")? {
+            self.server.bind_address = bind_address;
+        }
+        if let Some(url) = read_env("DATABASE_URL")? {
+            self.database.url = url;
+        }
+        if let Some(max_connections) = parse_env("DATABASE_MAX_CONNECTIONS")? {
+            self.database.max_connections = max_connections;
+        }
+        if let Some(min_connections) = parse_env("DATABASE_MIN_CONNECTIONS")? {
+            self.database.min_connections = min_connections;
+        }
+        if let Some(auto_migrate) = parse_env("DATABASE_AUTO_MIGRATE")? {
+            self.database.auto_migrate = auto_migrate;
+        }
+        if let Some(secret) = read_env("JWT_SECRET")? {
+            self.jwt.secret = secret;
+        }
+        if let Some(signing_kid) = read_env("JWT_SIGNING_KID")? {
+            self.jwt.signing_kid = Some(signing_kid);
+        }
+        if let Some(expiry_minutes) = parse_env("JWT_EXPIRY_MINUTES")? {
+            self.jwt.expiry_minutes = expiry_minutes;
+        }
+        if let Some(refresh_expiry_days) = parse_env("JWT_REFRESH_EXPIRY_DAYS")? {
+            self.jwt.refresh_expiry_days = refresh_expiry_days;
+        }
+        if let Some(issuer) = read_env("JWT_ISSUER")? {
+            self.jwt.issuer = issuer;
+        }
+        if let Some(audience) = read_env("JWT_AUDIENCE")? {
+            self.jwt.audience = audience;
+        }
+        if let Some(check_revocation) = parse_env("JWT_CHECK_REVOCATION")? {
+            self.jwt.check_revocation = check_revocation;
+        }
+        if let Some(origins) = read_env("CORS_ALLOWED_ORIGINS")? {
+            self.cors.allowed_origins = origins
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(str::to_owned)
+                .collect();
+        }
+        if let Some(format) = parse_env("LOG_FORMAT")? {
+            self.log.format = format;
+        }
+        if let Some(require_if_match) = parse_env("TICKETS_REQUIRE_IF_MATCH")? {
+            self.tickets.require_if_match = require_if_match;
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        self.validate_database()?;
+        self.validate_jwt_keys()?;
+        if self.jwt.expiry_minutes <= 0 {
+            return Err(ConfigError::Invalid {
+                field: "jwt.expiry_minutes",
+                reason: "must be greater than 0".to_owned(),
+            });
+        }
+        if self.jwt.refresh_expiry_days <= 0 {
+            return Err(ConfigError::Invalid {
+                field: "jwt.refresh_expiry_days",
+                reason: "must be greater than 0".to_owned(),
+            });
+        }
+        if self.jwt.issuer.is_empty() || self.jwt.audience.is_empty() {
+            return Err(ConfigError::Invalid {
+                field: "jwt.issuer",
+                reason: "issuer and audience must not be empty".to_owned(),
+            });
+        }
+        if self.cors.allowed_origins.is_empty() {
+            return Err(ConfigError::Invalid {
+                field: "cors.allowed_origins",
+                reason: "must list at least one origin, use \"*\" to allow any".to_owned(),
+            });
+        }
+        if let Some(origin) = self
+            .cors
+            .allowed_origins
+            .iter()
+            .find(|origin| origin.parse::<axum::http::HeaderValue>().is_err())
+        {
+            return Err(ConfigError::Invalid {
+                field: "cors.allowed_origins",
+                reason: format!("{origin:?} is not a valid origin"),
+            });
+        }
+        if let Some((from, _)) = self
+            .workflow
+            .transitions
+            .iter()
+            .find(|(from, targets)| targets.contains(from))
+        {
+            return Err(ConfigError::Invalid {
+                field: "workflow.transitions",
+                reason: format!("{:?} must not transition to itself", from.to_value()),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn validate_database(&self) -> Result<(), ConfigError> {
+        if self.database.url.is_empty() {
+            return Err(ConfigError::Invalid {
+                field: "database.url",
+                reason: "must be set (or provide $DATABASE_URL)".to_owned(),
+            });
+        }
+        if self.database.max_connections == 0 {
+            return Err(ConfigError::Invalid {
+                field: "database.max_connections",
+                reason: "must be greater than 0".to_owned(),
+            });
+        }
+        if self.database.min_connections > self.database.max_connections {
+            return Err(ConfigError::Invalid {
+                field: "database.min_connections",
+                reason: "must not exceed database.max_connections".to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn validate_jwt_keys(&self) -> Result<(), ConfigError> {
+        let keys = self.jwt.key_configs();
+        if keys.is_empty() {
+            return Err(ConfigError::Invalid {
+                field: "jwt.secret",
+                reason: "set jwt.secret (or $JWT_SECRET) or configure jwt.keys".to_owned(),
+            });
+        }
+
+        for (index, key) in keys.iter().enumerate() {
+            let key_error = |reason: &str| ConfigError::Key {
+                kid: key.kid.clone(),
+                reason: reason.to_owned(),
+            };
+
+            if key.kid.is_empty() {
+                return Err(key_error("kid must not be empty"));
+            }
+            if keys[..index].iter().any(|other| other.kid == key.kid) {
+                return Err(key_error("kid is used by more than one key"));
+            }
+
+            match key.algorithm {
+                JwtAlgorithm::HS256 => match &key.secret {
+                    Some(secret) if secret.len() >= 16 => {}
+                    Some(_) => return Err(key_error("secret must be at least 16 bytes")),
+                    None => return Err(key_error("HS256 keys need a secret")),
+                },
+                JwtAlgorithm::RS256 | JwtAlgorithm::EdDSA => {
+                    if key.public_key_path.is_none() {
+                        return Err(key_error("asymmetric keys need a public_key_path"));
+                    }
+                }
+            }
+        }
+
+        let Some(signing_kid) = self.jwt.signing_kid() else {
+            return Err(ConfigError::Invalid {
+                field: "jwt.signing_kid",
+                reason: "must be set when more than one key is configured".to_owned(),
+            });
+        };
+        let Some(signing_key) = keys.iter().find(|key| key.kid == signing_kid) else {
+            return Err(ConfigError::Invalid {
+                field: "jwt.signing_kid",
+                reason: format!("no key with kid {signing_kid:?}"),
+            });
+        };
+        if signing_key.algorithm != JwtAlgorithm::HS256 && signing_key.private_key_path.is_none() {
+            return Err(ConfigError::Key {
+                kid: signing_kid,
+                reason: "the signing key needs a private_key_path".to_owned(),
+            });
+        }
+        if signing_key.verify_until.is_some() {
+            return Err(ConfigError::Key {
+                kid: signing_kid,
+                reason: "the signing key cannot be retired with verify_until".to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+fn read_env(name: &'static str) -> Result<Option<String>, ConfigError> {
+    match env::var(name) {
+        Ok(value) => Ok(Some(value)),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(_)) => Err(ConfigError::Env {
+            name,
+            reason: "not valid unicode".to_owned(),
+        }),
+    }
+}
+
+fn parse_env<T>(name: &'static str) -> Result<Option<T>, ConfigError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    read_env(name)?
+        .map(|value| {
+            value.parse().map_err(|err| ConfigError::Env {
+                name,
+                reason: format!("{value:?}: {err}"),
+            })
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "0123456789abcdef";
+
+    fn parse(toml: &str) -> Result<Config, toml::de::Error> {
+        toml::from_str(toml)
+    }
+
+    fn valid(toml: &str) -> Config {
+        let config = parse(&format!(
+            "{toml}\n[database]\nurl = \"postgres://localhost/tickets\""
+        ))
+        .expect("config parses");
+        config.validate().expect("config is valid");
+        config
+    }
+
+    fn invalid(toml: &str) -> String {
+        let config = parse(&format!(
+            "{toml}\n[database]\nurl = \"postgres://localhost/tickets\""
+        ))
+        .expect("config parses");
+        config
+            .validate()
+            .expect_err("config is rejected")
+            .to_string()
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(parse("[server]\nport = 3000").is_err());
+        assert!(parse("[caching]\nenabled = true").is_err());
+        assert!(parse(&format!("[jwt]\nsecret = {SECRET:?}\nsecrets = []")).is_err());
+    }
+
+    #[test]
+    fn secret_shorthand_is_a_single_hs256_key() {
+        let config = valid(&format!("[jwt]\nsecret = {SECRET:?}"));
+
+        assert_eq!(config.jwt.signing_kid().as_deref(), Some("default"));
+        assert_eq!(config.jwt.key_configs()[0].algorithm, JwtAlgorithm::HS256);
+    }
+
+    #[test]
+    fn short_hs256_secret_is_rejected() {
+        assert_eq!(
+            invalid("[jwt]\nsecret = \"too-short\""),
+            "invalid jwt key \"default\": secret must be at least 16 bytes"
+        );
+        assert_eq!(
+            invalid(
+                "[[jwt.keys]]\nkid = \"k1\"\nalgorithm = \"HS256\"\nsecret = \"0123456789abcde\""
+            ),
+            "invalid jwt key \"k1\": secret must be at least 16 bytes"
+        );
+    }
+
+    #[test]
+    fn signing_kid_must_name_a_configured_key() {
+        let keys = format!(
+            "[[jwt.keys]]\nkid = \"k1\"\nalgorithm = \"HS256\"\nsecret = {SECRET:?}\n\
+             [[jwt.keys]]\nkid = \"k2\"\nalgorithm = \"HS256\"\nsecret = {SECRET:?}"
+        );
+
+        assert!(invalid(&keys).starts_with("invalid jwt.signing_kid: must be set"));
+        assert_eq!(
+            invalid(&format!("[jwt]\nsigning_kid = \"k3\"\n{keys}")),
+            "invalid jwt.signing_kid: no key with kid \"k3\""
+        );
+        valid(&format!("[jwt]\nsigning_kid = \"k2\"\n{keys}"));
+    }
+
+    #[test]
+    fn workflow_self_transition_is_rejected() {
+        assert_eq!(
+            invalid(&format!(
+                "[jwt]\nsecret = {SECRET:?}\n\
+                 [workflow.transitions]\nopen = [\"done\", \"open\"]"
+            )),
+            "invalid workflow.transitions: \"open\" must not transition to itself"
+        );
+    }
+
+    #[test]
+    fn migrate_only_needs_the_database() {
+        let config = parse("[database]\nurl = \"postgres://localhost/tickets\"").unwrap();
+
+        assert!(config.validate_database().is_ok());
+        assert!(config.validate().is_err());
+        assert!(Config::default().validate_database().is_err());
+    }
+
+    #[test]
+    fn env_overrides_the_file() {
+        let mut config = parse("[jwt]\nissuer = \"from-toml\"\naudience = \"from-toml\"").unwrap();
+
+        // SAFETY: no other test reads or writes this variable
+        unsafe { env::set_var("JWT_ISSUER", "from-env") };
+        let applied = config.apply_env();
+        unsafe { env::remove_var("JWT_ISSUER") };
+        applied.unwrap();
+
+        assert_eq!(config.jwt.issuer, "from-env");
+        assert_eq!(config.jwt.audience, "from-toml");
+    }
+
+    #[test]
+    fn debug_redacts_secrets() {
+        let config = valid(&format!(
+            "[jwt]\nsecret = {SECRET:?}\nsigning_kid = \"k1\"\n\
+             [[jwt.keys]]\nkid = \"k1\"\nalgorithm = \"HS256\"\nsecret = \"fedcba9876543210\""
+        ));
+        let debug = format!("{config:?}");
+
+        assert!(!debug.contains(SECRET), "{debug}");
+        assert!(!debug.contains("fedcba9876543210"), "{debug}");
+        assert!(debug.contains("<redacted>"), "{debug}");
+    }
+}
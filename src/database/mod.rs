@@ -2,5 +2,16 @@
 
 pub mod prelude;
 
+pub mod labels;
+pub mod project_members;
+pub mod projects;
+pub mod refresh_tokens;
+pub mod saved_views;
+pub mod sea_orm_active_enums;
+pub mod sessions;
+pub mod ticket_assignees;
+pub mod ticket_comments;
+pub mod ticket_events;
+pub mod ticket_labels;
 pub mod tickets;
 pub mod users;
@@ -1,5 +1,25 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.7
 
+#[allow(unused_imports)]
+pub use super::labels::Entity as Labels;
+#[allow(unused_imports)]
+pub use super::project_members::Entity as ProjectMembers;
+#[allow(unused_imports)]
+pub use super::projects::Entity as Projects;
+#[allow(unused_imports)]
+pub use super::refresh_tokens::Entity as RefreshTokens;
+#[allow(unused_imports)]
+pub use super::saved_views::Entity as SavedViews;
+#[allow(unused_imports)]
+pub use super::sessions::Entity as Sessions;
+#[allow(unused_imports)]
+pub use super::ticket_assignees::Entity as TicketAssignees;
+#[allow(unused_imports)]
+pub use super::ticket_comments::Entity as TicketComments;
+#[allow(unused_imports)]
+pub use super::ticket_events::Entity as TicketEvents;
+#[allow(unused_imports)]
+pub use super::ticket_labels::Entity as TicketLabels;
 #[allow(unused_imports)]
 pub use super::tickets::Entity as Tickets;
 #[allow(unused_imports)]
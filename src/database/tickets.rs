@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.7
 
+use super::sea_orm_active_enums::{TicketPriority, TicketStatus};
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
@@ -7,18 +8,38 @@ use sea_orm::entity::prelude::*;
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
-    pub priority: Option<String>,
+    pub priority: Option<TicketPriority>,
     pub title: String,
     pub completed_at: Option<DateTimeWithTimeZone>,
     #[sea_orm(column_type = "Text", nullable)]
     pub description: Option<String>,
     pub deleted_at: Option<DateTimeWithTimeZone>,
     pub user_id: Option<i32>,
-    pub in_progress: Option<bool>,
+    pub status: TicketStatus,
+    pub project_id: Option<i32>,
+    pub number: Option<i32>,
+    pub version: i32,
+    pub created_at: DateTimeWithTimeZone,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::projects::Entity",
+        from = "Column::ProjectId",
+        to = "super::projects::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Restrict"
+    )]
+    Projects,
+    #[sea_orm(has_many = "super::ticket_assignees::Entity")]
+    TicketAssignees,
+    #[sea_orm(has_many = "super::ticket_comments::Entity")]
+    TicketComments,
+    #[sea_orm(has_many = "super::ticket_events::Entity")]
+    TicketEvents,
+    #[sea_orm(has_many = "super::ticket_labels::Entity")]
+    TicketLabels,
     #[sea_orm(
         belongs_to = "super::users::Entity",
         from = "Column::UserId",
@@ -29,6 +50,45 @@ pub enum Relation {
     Users,
 }
 
+impl Related<super::projects::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Projects.def()
+    }
+}
+
+impl Related<super::ticket_assignees::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TicketAssignees.def()
+    }
+}
+
+impl Related<super::ticket_comments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TicketComments.def()
+    }
+}
+
+impl Related<super::ticket_events::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TicketEvents.def()
+    }
+}
+
+impl Related<super::ticket_labels::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TicketLabels.def()
+    }
+}
+
+impl Related<super::labels::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::ticket_labels::Relation::Labels.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::ticket_labels::Relation::Tickets.def().rev())
+    }
+}
+
 impl Related<super::users::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Users.def()
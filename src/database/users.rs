@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.7
 
+use super::sea_orm_active_enums::UserRole;
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
@@ -11,16 +12,64 @@ pub struct Model {
     pub username: String,
     pub password: String,
     pub deleted_at: Option<DateTimeWithTimeZone>,
-    #[sea_orm(column_type = "Text", nullable)]
-    pub token: Option<String>,
+    pub role: UserRole,
+    pub default_view_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::project_members::Entity")]
+    ProjectMembers,
+    #[sea_orm(
+        belongs_to = "super::saved_views::Entity",
+        from = "Column::DefaultViewId",
+        to = "super::saved_views::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    SavedViews,
+    #[sea_orm(has_many = "super::sessions::Entity")]
+    Sessions,
+    #[sea_orm(has_many = "super::ticket_assignees::Entity")]
+    TicketAssignees,
+    #[sea_orm(has_many = "super::ticket_comments::Entity")]
+    TicketComments,
+    #[sea_orm(has_many = "super::ticket_events::Entity")]
+    TicketEvents,
     #[sea_orm(has_many = "super::tickets::Entity")]
     Tickets,
 }
 
+impl Related<super::project_members::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProjectMembers.def()
+    }
+}
+
+impl Related<super::sessions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Sessions.def()
+    }
+}
+
+impl Related<super::ticket_assignees::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TicketAssignees.def()
+    }
+}
+
+impl Related<super::ticket_comments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TicketComments.def()
+    }
+}
+
+impl Related<super::ticket_events::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TicketEvents.def()
+    }
+}
+
 impl Related<super::tickets::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Tickets.def()
@@ -1,25 +1,60 @@
+pub mod config;
 mod database;
 mod middleware;
+pub mod migration;
+mod policy;
 mod routes;
 mod utils;
 
+use config::{Config, DatabaseConfig};
+use migration::{MigrateCommand, Migrator};
 use routes::routes;
-use sea_orm::Database;
+use sea_orm::{ConnectOptions, Database, DatabaseConnection, DbErr};
+use sea_orm_migration::MigratorTrait;
 use tokio::net::TcpListener;
+use utils::jwt_keys::JwtKeys;
 
-pub async fn run(db_uri: &str) {
-    utils::logging::init_tracing();
-    tracing::info!("Initialized server");
+async fn connect(database_config: &DatabaseConfig) -> DatabaseConnection {
+    let mut connect_options = ConnectOptions::new(&database_config.url);
+    connect_options
+        .max_connections(database_config.max_connections)
+        .min_connections(database_config.min_connections);
 
-    let db_conn = Database::connect(db_uri).await.unwrap();
+    let db_conn = Database::connect(connect_options).await.unwrap();
     tracing::info!("Database connected");
+    db_conn
+}
+
+pub async fn run(config: Config) {
+    utils::logging::init_tracing(&config.log);
+    tracing::info!("Initialized server");
+
+    let jwt_keys = JwtKeys::from_config(&config.jwt)
+        .unwrap_or_else(|err| panic!("Failed to load JWT keys: {err}"));
 
-    let listener = TcpListener::bind("0.0.0.0:3000").await.unwrap();
+    let db_conn = connect(&config.database).await;
+
+    if config.database.auto_migrate {
+        Migrator::up(&db_conn, None)
+            .await
+            .unwrap_or_else(|err| panic!("Failed to apply migrations: {err}"));
+        tracing::info!("Migrations applied");
+    }
+
+    let listener = TcpListener::bind(config.server.bind_address).await.unwrap();
     tracing::info!("listening on {}", listener.local_addr().unwrap());
 
-    let app_router = routes(db_conn);
+    let app_router = routes(db_conn, config, jwt_keys);
 
     axum::serve(listener, app_router.into_make_service())
         .await
         .unwrap()
 }
+
+/// Runs a `migrate` subcommand against the configured database
+pub async fn migrate(config: Config, command: MigrateCommand) -> Result<(), DbErr> {
+    utils::logging::init_tracing(&config.log);
+
+    let db_conn = connect(&config.database).await;
+    command.run(&db_conn).await
+}
@@ -1,11 +1,45 @@
-use axum_ticketstore::run;
+use axum_ticketstore::{
+    config::{Config, ConfigError},
+    migrate,
+    migration::MigrateCommand,
+    run,
+};
 use dotenvy::dotenv;
-use dotenvy_macro::dotenv;
+
+const USAGE: &str =
+    "Usage: axum-ticketstore [serve | migrate [up [N] | down [N] | status | fresh]]";
+
+fn valid_config(config: Result<Config, ConfigError>) -> Config {
+    config.unwrap_or_else(|err| {
+        eprintln!("Invalid configuration: {err}");
+        std::process::exit(1)
+    })
+}
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
-    let db_uri = dotenv!("DATABASE_URL");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        None | Some("serve") => run(valid_config(Config::load())).await,
+        Some("migrate") => {
+            let command = MigrateCommand::parse(&args[1..]).unwrap_or_else(|err| {
+                eprintln!("{err}\n{USAGE}");
+                std::process::exit(2)
+            });
 
-    run(db_uri).await
+            // Migrations only need the database, a half-configured server can
+            // still be migrated
+            let config = valid_config(Config::load_for_migrate());
+            if let Err(err) = migrate(config, command).await {
+                eprintln!("Migration failed: {err}");
+                std::process::exit(1)
+            }
+        }
+        Some(other) => {
+            eprintln!("Unknown command {other:?}\n{USAGE}");
+            std::process::exit(2)
+        }
+    }
 }
@@ -6,13 +6,32 @@ use axum::{
     response::Response,
 };
 use axum_extra::headers::{Authorization, HeaderMapExt, authorization::Bearer};
+use chrono::Utc;
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::sync::Arc;
 
-use crate::{database::users, utils::api_error::ApiError};
-use crate::{database::users::Entity as Users, utils::jwt::is_valid};
+use crate::{
+    config::Config,
+    database::{sea_orm_active_enums::UserRole, sessions},
+    utils::api_error::ApiError,
+};
+use crate::{
+    database::sessions::Entity as Sessions,
+    utils::{jwt::is_valid, jwt_keys::JwtKeys},
+};
+
+/// Identity of the caller, taken from the access token claims
+#[derive(Clone, Debug)]
+pub struct AuthUser {
+    pub id: i32,
+    pub role: UserRole,
+    pub session_id: i32,
+}
 
 pub async fn guard(
     State(db_conn): State<DatabaseConnection>,
+    State(config): State<Arc<Config>>,
+    State(jwt_keys): State<Arc<JwtKeys>>,
     mut request: Request<Body>,
     next: Next,
 ) -> Result<Response, ApiError> {
@@ -24,30 +43,39 @@ pub async fn guard(
         .token()
         .to_owned();
 
-    // Find the associated user based on their token
-    let user = Users::find()
-        .filter(users::Column::Token.eq(Some(req_token.clone())))
-        .one(&db_conn)
-        .await
-        .map_err(|_err| {
-            ApiError::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "No user associated with bearer token",
-            )
-        })?;
-
-    let Some(user_model) = user else {
-        return Err(ApiError::new(
-            StatusCode::UNAUTHORIZED,
-            "Unauthorized, please log in",
-        ));
-    };
-
-    // Validate the token
-    // Validate post db connection to obfuscate delay in case of timing attack
-    is_valid(&req_token)?;
-
-    request.extensions_mut().insert(user_model);
+    let claims = is_valid(&req_token, &config.jwt, &jwt_keys)?;
+    let user_id = claims.user_id()?;
+
+    // Make sure the session behind the token was not logged out or revoked
+    if config.jwt.check_revocation {
+        let session = Sessions::find_by_id(claims.sid)
+            .filter(sessions::Column::UserId.eq(user_id))
+            .filter(sessions::Column::AccessJti.eq(claims.jti.as_str()))
+            .filter(sessions::Column::RevokedAt.is_null())
+            .filter(sessions::Column::ExpiresAt.gt(Utc::now().fixed_offset()))
+            .one(&db_conn)
+            .await
+            .map_err(|err| {
+                tracing::error!("Error: {:?}", err);
+                ApiError::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to verify session",
+                )
+            })?;
+
+        if session.is_none() {
+            return Err(
+                ApiError::new(StatusCode::UNAUTHORIZED, "Unauthorized, please log in")
+                    .with_code("session_revoked"),
+            );
+        }
+    }
+
+    request.extensions_mut().insert(AuthUser {
+        id: user_id,
+        role: claims.role,
+        session_id: claims.sid,
+    });
 
     Ok(next.run(request).await)
 }
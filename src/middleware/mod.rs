@@ -1 +1,3 @@
 pub mod guard;
+pub mod request_context;
+pub mod require_role;
@@ -0,0 +1,66 @@
+use axum::{
+    body::Body,
+    http::{HeaderName, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+
+use crate::utils::ids::random_id;
+
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client supplied request id we pass along
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_CONTEXT: RequestContext;
+}
+
+/// Per request details errors are reported with
+#[derive(Clone, Debug)]
+pub struct RequestContext {
+    pub request_id: String,
+    pub path: String,
+}
+
+impl RequestContext {
+    /// Context of the request being handled, `None` outside of `request_context`
+    pub fn current() -> Option<Self> {
+        REQUEST_CONTEXT.try_with(Clone::clone).ok()
+    }
+}
+
+/// Tags every request with an id, reusing the caller's `x-request-id` when it
+/// looks sane, and echoes it back on the response
+pub async fn request_context(request: Request<Body>, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= MAX_REQUEST_ID_LENGTH)
+        .map(str::to_owned)
+        .unwrap_or_else(random_id);
+
+    let context = RequestContext {
+        request_id: request_id.clone(),
+        path: request.uri().path().to_owned(),
+    };
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %context.path,
+    );
+
+    let mut response = REQUEST_CONTEXT
+        .scope(context, next.run(request).instrument(span))
+        .await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER.clone(), value);
+    }
+    response
+}
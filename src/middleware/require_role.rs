@@ -0,0 +1,38 @@
+use axum::{
+    Extension,
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+use super::guard::AuthUser;
+use crate::{database::sea_orm_active_enums::UserRole, utils::api_error::ApiError};
+
+/// Rejects users whose role is below the one given as state
+///
+/// Must be layered inside `guard` so the user extension is present:
+/// `.route_layer(middleware::from_fn_with_state(UserRole::Admin, require_role))`
+pub async fn require_role(
+    State(required): State<UserRole>,
+    Extension(user): Extension<AuthUser>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if !user.role.permits(required) {
+        tracing::warn!(
+            "User {:?} with role {:?} denied route requiring {:?}",
+            user.id,
+            user.role,
+            required
+        );
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "You do not have the required role for this action",
+        )
+        .with_code("insufficient_role"));
+    }
+
+    Ok(next.run(request).await)
+}
@@ -0,0 +1,90 @@
+use sea_orm_migration::{prelude::*, sea_query::extension::postgres::Type};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Databases created from the old seed/init.sql already have a users
+        // table, which is adopted and brought forward below
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "DO $$ BEGIN
+                    CREATE TYPE user_role AS ENUM ('admin', 'member', 'read_only');
+                EXCEPTION
+                    WHEN duplicate_object THEN NULL;
+                END $$",
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Users::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Users::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Users::Username)
+                            .string_len(64)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Users::Password).string_len(128).not_null())
+                    .col(ColumnDef::new(Users::DeletedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(Users::Role)
+                            .custom(UserRole)
+                            .not_null()
+                            .default(Expr::val("member").as_enum(UserRole)),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The init.sql table has no role and still carries the token column
+        // sessions replaced, neither statement touches a table created above
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE users ADD COLUMN IF NOT EXISTS role user_role NOT NULL DEFAULT 'member'",
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared("ALTER TABLE users DROP COLUMN IF EXISTS token")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Users::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(UserRole).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Users {
+    Table,
+    Id,
+    Username,
+    Password,
+    DeletedAt,
+    Role,
+}
+
+#[derive(DeriveIden)]
+struct UserRole;
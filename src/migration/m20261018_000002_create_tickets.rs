@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20261018_000001_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Matches the tickets table of the old seed/init.sql column for column,
+        // so such a table is adopted as is and the later migrations carry it
+        // forward like any other
+        manager
+            .create_table(
+                Table::create()
+                    .table(Tickets::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Tickets::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Tickets::Priority).string_len(4))
+                    .col(ColumnDef::new(Tickets::Title).string_len(255).not_null())
+                    .col(ColumnDef::new(Tickets::CompletedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(Tickets::Description).text())
+                    .col(ColumnDef::new(Tickets::DeletedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(Tickets::UserId).integer())
+                    .col(ColumnDef::new(Tickets::InProgress).boolean().default(false))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_users")
+                            .from(Tickets::Table, Tickets::UserId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Tickets::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Tickets {
+    Table,
+    Id,
+    Priority,
+    Title,
+    CompletedAt,
+    Description,
+    DeletedAt,
+    UserId,
+    InProgress,
+}
@@ -0,0 +1,83 @@
+use sea_orm_migration::prelude::*;
+
+use super::{m20261018_000001_create_users::Users, m20261018_000002_create_tickets::Tickets};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TicketComments::Table)
+                    .col(
+                        ColumnDef::new(TicketComments::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TicketComments::TicketId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TicketComments::UserId).integer().not_null())
+                    .col(ColumnDef::new(TicketComments::Body).text().not_null())
+                    .col(
+                        ColumnDef::new(TicketComments::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(TicketComments::EditedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(TicketComments::DeletedAt).timestamp_with_time_zone())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_tickets")
+                            .from(TicketComments::Table, TicketComments::TicketId)
+                            .to(Tickets::Table, Tickets::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_users")
+                            .from(TicketComments::Table, TicketComments::UserId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ticket_comments_ticket_id_created_at")
+                    .table(TicketComments::Table)
+                    .col(TicketComments::TicketId)
+                    .col(TicketComments::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TicketComments::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum TicketComments {
+    Table,
+    Id,
+    TicketId,
+    UserId,
+    Body,
+    CreatedAt,
+    EditedAt,
+    DeletedAt,
+}
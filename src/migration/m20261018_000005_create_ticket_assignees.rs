@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+use super::{m20261018_000001_create_users::Users, m20261018_000002_create_tickets::Tickets};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TicketAssignees::Table)
+                    .col(
+                        ColumnDef::new(TicketAssignees::TicketId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TicketAssignees::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(TicketAssignees::AssignedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(TicketAssignees::TicketId)
+                            .col(TicketAssignees::UserId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_tickets")
+                            .from(TicketAssignees::Table, TicketAssignees::TicketId)
+                            .to(Tickets::Table, Tickets::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_users")
+                            .from(TicketAssignees::Table, TicketAssignees::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The primary key covers lookups by ticket, this one "assigned to me"
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ticket_assignees_user_id")
+                    .table(TicketAssignees::Table)
+                    .col(TicketAssignees::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TicketAssignees::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum TicketAssignees {
+    Table,
+    TicketId,
+    UserId,
+    AssignedAt,
+}
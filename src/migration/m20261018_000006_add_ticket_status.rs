@@ -0,0 +1,107 @@
+use sea_orm_migration::{prelude::*, sea_query::extension::postgres::Type};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(TicketStatus)
+                    .values([
+                        TicketStatusVariant::Open,
+                        TicketStatusVariant::InProgress,
+                        TicketStatusVariant::Blocked,
+                        TicketStatusVariant::Done,
+                        TicketStatusVariant::Cancelled,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tickets::Table)
+                    .add_column(
+                        ColumnDef::new(Tickets::Status)
+                            .custom(TicketStatus)
+                            .not_null()
+                            .default(Expr::val("open").as_enum(TicketStatus)),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Completed wins over in progress, the old flags allowed both at once
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE tickets SET status = CASE
+                    WHEN completed_at IS NOT NULL THEN 'done'::ticket_status
+                    WHEN in_progress THEN 'in_progress'::ticket_status
+                    ELSE 'open'::ticket_status
+                END",
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tickets::Table)
+                    .drop_column(Tickets::InProgress)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tickets::Table)
+                    .add_column(ColumnDef::new(Tickets::InProgress).boolean().default(false))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared("UPDATE tickets SET in_progress = (status = 'in_progress')")
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tickets::Table)
+                    .drop_column(Tickets::Status)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(TicketStatus).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tickets {
+    Table,
+    Status,
+    InProgress,
+}
+
+#[derive(DeriveIden)]
+struct TicketStatus;
+
+#[derive(DeriveIden)]
+enum TicketStatusVariant {
+    Open,
+    InProgress,
+    Blocked,
+    Done,
+    Cancelled,
+}
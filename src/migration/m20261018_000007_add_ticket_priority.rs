@@ -0,0 +1,76 @@
+use sea_orm_migration::{prelude::*, sea_query::extension::postgres::Type};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Declared from lowest to highest so ORDER BY sorts by urgency
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(TicketPriority)
+                    .values([
+                        TicketPriorityVariant::Low,
+                        TicketPriorityVariant::Medium,
+                        TicketPriorityVariant::High,
+                        TicketPriorityVariant::Urgent,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        // Databases created from the old seed/init.sql carry a `DEFAULT NULL`
+        // that cannot be cast to the new type
+        manager
+            .get_connection()
+            .execute_unprepared("ALTER TABLE tickets ALTER COLUMN priority DROP DEFAULT")
+            .await?;
+
+        // The old letter grades map onto the new levels, anything else was
+        // never meaningful and is dropped
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE tickets ALTER COLUMN priority TYPE ticket_priority USING CASE upper(priority)
+                    WHEN 'A' THEN 'urgent'::ticket_priority
+                    WHEN 'B' THEN 'high'::ticket_priority
+                    WHEN 'C' THEN 'medium'::ticket_priority
+                    WHEN 'D' THEN 'low'::ticket_priority
+                END",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE tickets ALTER COLUMN priority TYPE VARCHAR(4) USING CASE priority
+                    WHEN 'urgent' THEN 'A'
+                    WHEN 'high' THEN 'B'
+                    WHEN 'medium' THEN 'C'
+                    WHEN 'low' THEN 'D'
+                END",
+            )
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(TicketPriority).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+struct TicketPriority;
+
+#[derive(DeriveIden)]
+enum TicketPriorityVariant {
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
@@ -0,0 +1,105 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20261018_000002_create_tickets::Tickets;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Labels::Table)
+                    .col(
+                        ColumnDef::new(Labels::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Labels::Name)
+                            .string_len(64)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Labels::Colour).string_len(7).not_null())
+                    .col(
+                        ColumnDef::new(Labels::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(TicketLabels::Table)
+                    .col(ColumnDef::new(TicketLabels::TicketId).integer().not_null())
+                    .col(ColumnDef::new(TicketLabels::LabelId).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(TicketLabels::TicketId)
+                            .col(TicketLabels::LabelId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_tickets")
+                            .from(TicketLabels::Table, TicketLabels::TicketId)
+                            .to(Tickets::Table, Tickets::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_labels")
+                            .from(TicketLabels::Table, TicketLabels::LabelId)
+                            .to(Labels::Table, Labels::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The primary key covers labels of a ticket, this one tickets of a label
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ticket_labels_label_id")
+                    .table(TicketLabels::Table)
+                    .col(TicketLabels::LabelId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TicketLabels::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Labels::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Labels {
+    Table,
+    Id,
+    Name,
+    Colour,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum TicketLabels {
+    Table,
+    TicketId,
+    LabelId,
+}
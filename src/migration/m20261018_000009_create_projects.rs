@@ -0,0 +1,174 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20261018_000001_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Projects::Table)
+                    .col(
+                        ColumnDef::new(Projects::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Projects::Key)
+                            .string_len(10)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Projects::Name).string_len(255).not_null())
+                    .col(ColumnDef::new(Projects::Description).text())
+                    .col(
+                        ColumnDef::new(Projects::NextNumber)
+                            .integer()
+                            .not_null()
+                            .default(1),
+                    )
+                    .col(
+                        ColumnDef::new(Projects::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProjectMembers::Table)
+                    .col(
+                        ColumnDef::new(ProjectMembers::ProjectId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ProjectMembers::UserId).integer().not_null())
+                    .col(
+                        ColumnDef::new(ProjectMembers::JoinedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(ProjectMembers::ProjectId)
+                            .col(ProjectMembers::UserId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_projects")
+                            .from(ProjectMembers::Table, ProjectMembers::ProjectId)
+                            .to(Projects::Table, Projects::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_users")
+                            .from(ProjectMembers::Table, ProjectMembers::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_project_members_user_id")
+                    .table(ProjectMembers::Table)
+                    .col(ProjectMembers::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Tickets created before projects existed stay outside of any project
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tickets::Table)
+                    .add_column(ColumnDef::new(Tickets::ProjectId).integer())
+                    .add_column(ColumnDef::new(Tickets::Number).integer())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_tickets_projects")
+                            .from_tbl(Tickets::Table)
+                            .from_col(Tickets::ProjectId)
+                            .to_tbl(Projects::Table)
+                            .to_col(Projects::Id)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_tickets_project_id_number")
+                    .table(Tickets::Table)
+                    .col(Tickets::ProjectId)
+                    .col(Tickets::Number)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tickets::Table)
+                    .drop_foreign_key(Alias::new("fk_tickets_projects"))
+                    .drop_column(Tickets::ProjectId)
+                    .drop_column(Tickets::Number)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ProjectMembers::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Projects::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Projects {
+    Table,
+    Id,
+    Key,
+    Name,
+    Description,
+    NextNumber,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ProjectMembers {
+    Table,
+    ProjectId,
+    UserId,
+    JoinedAt,
+}
+
+#[derive(DeriveIden)]
+enum Tickets {
+    Table,
+    ProjectId,
+    Number,
+}
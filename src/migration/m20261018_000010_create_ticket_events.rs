@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+
+use super::{m20261018_000001_create_users::Users, m20261018_000002_create_tickets::Tickets};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TicketEvents::Table)
+                    .col(
+                        ColumnDef::new(TicketEvents::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TicketEvents::TicketId).integer().not_null())
+                    .col(ColumnDef::new(TicketEvents::ActorId).integer())
+                    .col(
+                        ColumnDef::new(TicketEvents::Field)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TicketEvents::OldValue).json_binary())
+                    .col(ColumnDef::new(TicketEvents::NewValue).json_binary())
+                    .col(
+                        ColumnDef::new(TicketEvents::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_tickets")
+                            .from(TicketEvents::Table, TicketEvents::TicketId)
+                            .to(Tickets::Table, Tickets::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    // Keep the trail when an account is removed for good
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_users")
+                            .from(TicketEvents::Table, TicketEvents::ActorId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // History is always read per ticket in chronological order
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ticket_events_ticket_id_created_at")
+                    .table(TicketEvents::Table)
+                    .col(TicketEvents::TicketId)
+                    .col(TicketEvents::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TicketEvents::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TicketEvents {
+    Table,
+    Id,
+    TicketId,
+    ActorId,
+    Field,
+    OldValue,
+    NewValue,
+    CreatedAt,
+}
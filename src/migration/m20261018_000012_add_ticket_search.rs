@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Generated columns keep the vectors in step with every write without
+        // triggers. Title matches weigh more than description matches.
+        db.execute_unprepared(
+            "ALTER TABLE tickets ADD COLUMN search tsvector GENERATED ALWAYS AS (
+                setweight(to_tsvector('english', title), 'A')
+                || setweight(to_tsvector('english', coalesce(description, '')), 'B')
+            ) STORED",
+        )
+        .await?;
+        db.execute_unprepared(
+            "ALTER TABLE ticket_comments ADD COLUMN search tsvector GENERATED ALWAYS AS (
+                to_tsvector('english', body)
+            ) STORED",
+        )
+        .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_tickets_search")
+                    .table(Tickets::Table)
+                    .col(Tickets::Search)
+                    .index_type(IndexType::Custom(Alias::new("GIN").into_iden()))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ticket_comments_search")
+                    .table(TicketComments::Table)
+                    .col(TicketComments::Search)
+                    .index_type(IndexType::Custom(Alias::new("GIN").into_iden()))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Dropping the columns drops their indexes too
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TicketComments::Table)
+                    .drop_column(TicketComments::Search)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tickets::Table)
+                    .drop_column(Tickets::Search)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tickets {
+    Table,
+    Search,
+}
+
+#[derive(DeriveIden)]
+enum TicketComments {
+    Table,
+    Search,
+}
@@ -0,0 +1,109 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SavedViews::Table)
+                    .col(
+                        ColumnDef::new(SavedViews::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(SavedViews::UserId).integer().not_null())
+                    .col(ColumnDef::new(SavedViews::Name).string_len(64).not_null())
+                    .col(ColumnDef::new(SavedViews::Filters).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(SavedViews::Shared)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(SavedViews::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_saved_views_users")
+                            .from(SavedViews::Table, SavedViews::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_saved_views_user_id_name")
+                    .table(SavedViews::Table)
+                    .col(SavedViews::UserId)
+                    .col(SavedViews::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(ColumnDef::new(Users::DefaultViewId).integer())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_users_default_view")
+                            .from_tbl(Users::Table)
+                            .from_col(Users::DefaultViewId)
+                            .to_tbl(SavedViews::Table)
+                            .to_col(SavedViews::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::DefaultViewId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(SavedViews::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SavedViews {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Filters,
+    Shared,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+    DefaultViewId,
+}
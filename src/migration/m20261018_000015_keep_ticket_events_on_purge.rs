@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Purging a ticket detaches its history instead of deleting it
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TicketEvents::Table)
+                    .drop_foreign_key(Alias::new("fk_tickets"))
+                    .modify_column(ColumnDef::new(TicketEvents::TicketId).integer().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_tickets")
+                            .from_tbl(TicketEvents::Table)
+                            .from_col(TicketEvents::TicketId)
+                            .to_tbl(Tickets::Table)
+                            .to_col(Tickets::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The history of purged tickets has nothing left to point at
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(TicketEvents::Table)
+                    .and_where(Expr::col(TicketEvents::TicketId).is_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TicketEvents::Table)
+                    .drop_foreign_key(Alias::new("fk_tickets"))
+                    .modify_column(ColumnDef::new(TicketEvents::TicketId).integer().not_null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_tickets")
+                            .from_tbl(TicketEvents::Table)
+                            .from_col(TicketEvents::TicketId)
+                            .to_tbl(Tickets::Table)
+                            .to_col(Tickets::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TicketEvents {
+    Table,
+    TicketId,
+}
+
+#[derive(DeriveIden)]
+enum Tickets {
+    Table,
+    Id,
+}
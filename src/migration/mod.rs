@@ -0,0 +1,159 @@
+use sea_orm::DatabaseConnection;
+use sea_orm_migration::prelude::*;
+
+mod m20261018_000001_create_users;
+mod m20261018_000002_create_tickets;
+mod m20261018_000003_create_sessions;
+mod m20261018_000004_create_ticket_comments;
+mod m20261018_000005_create_ticket_assignees;
+mod m20261018_000006_add_ticket_status;
+mod m20261018_000007_add_ticket_priority;
+mod m20261018_000008_create_labels;
+mod m20261018_000009_create_projects;
+mod m20261018_000010_create_ticket_events;
+mod m20261018_000011_add_ticket_version;
+mod m20261018_000012_add_ticket_search;
+mod m20261018_000013_add_ticket_created_at;
+mod m20261018_000014_create_saved_views;
+mod m20261018_000015_keep_ticket_events_on_purge;
+
+/// Versioned schema changes, applied in order and recorded in `seaql_migrations`
+///
+/// The entities in `database` mirror the schema these produce, a schema change
+/// ships as a new migration together with the matching entity change.
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20261018_000001_create_users::Migration),
+            Box::new(m20261018_000002_create_tickets::Migration),
+            Box::new(m20261018_000003_create_sessions::Migration),
+            Box::new(m20261018_000004_create_ticket_comments::Migration),
+            Box::new(m20261018_000005_create_ticket_assignees::Migration),
+            Box::new(m20261018_000006_add_ticket_status::Migration),
+            Box::new(m20261018_000007_add_ticket_priority::Migration),
+            Box::new(m20261018_000008_create_labels::Migration),
+            Box::new(m20261018_000009_create_projects::Migration),
+            Box::new(m20261018_000010_create_ticket_events::Migration),
+            Box::new(m20261018_000011_add_ticket_version::Migration),
+            Box::new(m20261018_000012_add_ticket_search::Migration),
+            Box::new(m20261018_000013_add_ticket_created_at::Migration),
+            Box::new(m20261018_000014_create_saved_views::Migration),
+            Box::new(m20261018_000015_keep_ticket_events_on_purge::Migration),
+        ]
+    }
+}
+
+/// Arguments of the `migrate` subcommand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrateCommand {
+    /// Apply pending migrations, all of them unless a count is given
+    Up(Option<u32>),
+    /// Roll back applied migrations, one unless a count is given
+    Down(Option<u32>),
+    Status,
+    /// Drop every table and apply all migrations again
+    Fresh,
+}
+
+impl MigrateCommand {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let steps = |arg: Option<&String>| {
+            arg.map(|steps| {
+                steps
+                    .parse()
+                    .map_err(|_err| format!("Invalid number of steps {steps:?}"))
+            })
+            .transpose()
+        };
+
+        let command = match args.first().map(String::as_str) {
+            None | Some("up") => Self::Up(steps(args.get(1))?),
+            Some("down") => Self::Down(Some(steps(args.get(1))?.unwrap_or(1))),
+            Some("status") => Self::Status,
+            Some("fresh") => Self::Fresh,
+            Some(other) => return Err(format!("Unknown migrate command {other:?}")),
+        };
+
+        let max_args = match command {
+            Self::Up(_) | Self::Down(_) => 2,
+            Self::Status | Self::Fresh => 1,
+        };
+        if args.len() > max_args {
+            return Err(format!("Unexpected argument {:?}", args[max_args]));
+        }
+
+        Ok(command)
+    }
+
+    pub async fn run(self, db_conn: &DatabaseConnection) -> Result<(), DbErr> {
+        match self {
+            Self::Up(steps) => Migrator::up(db_conn, steps).await,
+            Self::Down(steps) => Migrator::down(db_conn, steps).await,
+            Self::Status => Migrator::status(db_conn).await,
+            Self::Fresh => Migrator::fresh(db_conn).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<MigrateCommand, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        MigrateCommand::parse(&args)
+    }
+
+    #[test]
+    fn up_applies_everything_unless_counted() {
+        assert_eq!(parse(&[]), Ok(MigrateCommand::Up(None)));
+        assert_eq!(parse(&["up"]), Ok(MigrateCommand::Up(None)));
+        assert_eq!(parse(&["up", "3"]), Ok(MigrateCommand::Up(Some(3))));
+    }
+
+    #[test]
+    fn down_rolls_back_one_unless_counted() {
+        assert_eq!(parse(&["down"]), Ok(MigrateCommand::Down(Some(1))));
+        assert_eq!(parse(&["down", "2"]), Ok(MigrateCommand::Down(Some(2))));
+    }
+
+    #[test]
+    fn status_and_fresh() {
+        assert_eq!(parse(&["status"]), Ok(MigrateCommand::Status));
+        assert_eq!(parse(&["fresh"]), Ok(MigrateCommand::Fresh));
+    }
+
+    #[test]
+    fn steps_must_be_a_count() {
+        for steps in ["x", "-1", "1.5", ""] {
+            assert_eq!(
+                parse(&["down", steps]),
+                Err(format!("Invalid number of steps {steps:?}"))
+            );
+        }
+        assert!(parse(&["up", "all"]).is_err());
+    }
+
+    #[test]
+    fn unknown_verbs_are_rejected() {
+        assert_eq!(
+            parse(&["sideways"]),
+            Err("Unknown migrate command \"sideways\"".to_owned())
+        );
+        assert!(parse(&["UP"]).is_err());
+        assert!(parse(&["--fresh"]).is_err());
+    }
+
+    #[test]
+    fn extra_arguments_are_rejected() {
+        assert_eq!(
+            parse(&["fresh", "--force"]),
+            Err("Unexpected argument \"--force\"".to_owned())
+        );
+        assert!(parse(&["status", "now"]).is_err());
+        assert!(parse(&["down", "1", "2"]).is_err());
+    }
+}
@@ -0,0 +1,58 @@
+use std::fmt;
+
+use axum::http::StatusCode;
+
+use crate::{
+    database::{sea_orm_active_enums::UserRole, ticket_comments},
+    middleware::guard::AuthUser,
+    utils::api_error::ApiError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentAction {
+    Edit,
+    Delete,
+}
+
+impl fmt::Display for CommentAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self {
+            CommentAction::Edit => "edit",
+            CommentAction::Delete => "delete",
+        };
+        write!(f, "{action}")
+    }
+}
+
+/// Decides whether `user` may perform `action` on `comment`
+///
+/// Reading and adding comments follows the ticket's own rules, changing one
+/// is reserved to its author and admins
+pub fn can(user: &AuthUser, comment: &ticket_comments::Model, _action: CommentAction) -> bool {
+    match user.role {
+        UserRole::Admin => true,
+        UserRole::ReadOnly => false,
+        UserRole::Member => comment.user_id == user.id,
+    }
+}
+
+pub fn authorize(
+    user: &AuthUser,
+    comment: &ticket_comments::Model,
+    action: CommentAction,
+) -> Result<(), ApiError> {
+    if can(user, comment, action) {
+        Ok(())
+    } else {
+        tracing::warn!(
+            "User {:?} denied {} on comment {:?}",
+            user.id,
+            action,
+            comment.id
+        );
+        Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            format!("You are not allowed to {action} this comment"),
+        ))
+    }
+}
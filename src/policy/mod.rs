@@ -0,0 +1,4 @@
+pub mod comment;
+pub mod project;
+pub mod role;
+pub mod ticket;
@@ -0,0 +1,59 @@
+use std::fmt;
+
+use axum::http::StatusCode;
+
+use crate::{
+    database::{projects, sea_orm_active_enums::UserRole},
+    middleware::guard::AuthUser,
+    utils::api_error::ApiError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectAction {
+    CreateTicket,
+}
+
+impl fmt::Display for ProjectAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self {
+            ProjectAction::CreateTicket => "create tickets in",
+        };
+        write!(f, "{action}")
+    }
+}
+
+/// Decides whether `user` may perform `action` on a project, `members` being
+/// the ids of the project's members
+///
+/// Tickets inside a project are read and changed under the ticket rules, only
+/// filing new ones is limited to the project's members and admins
+pub fn can(user: &AuthUser, members: &[i32], _action: ProjectAction) -> bool {
+    match user.role {
+        UserRole::Admin => true,
+        UserRole::ReadOnly => false,
+        UserRole::Member => members.contains(&user.id),
+    }
+}
+
+pub fn authorize(
+    user: &AuthUser,
+    project: &projects::Model,
+    members: &[i32],
+    action: ProjectAction,
+) -> Result<(), ApiError> {
+    if can(user, members, action) {
+        Ok(())
+    } else {
+        tracing::warn!(
+            "User {:?} denied {} on project {:?}",
+            user.id,
+            action,
+            project.key
+        );
+        Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            format!("You are not allowed to {action} this project"),
+        )
+        .with_code("not_project_member"))
+    }
+}
@@ -0,0 +1,16 @@
+use crate::database::sea_orm_active_enums::UserRole;
+
+impl UserRole {
+    fn rank(self) -> u8 {
+        match self {
+            UserRole::ReadOnly => 0,
+            UserRole::Member => 1,
+            UserRole::Admin => 2,
+        }
+    }
+
+    /// Whether a user holding `self` satisfies a route requiring `required`
+    pub fn permits(self, required: UserRole) -> bool {
+        self.rank() >= required.rank()
+    }
+}
@@ -0,0 +1,109 @@
+use std::fmt;
+
+use axum::http::StatusCode;
+use sea_orm::{ColumnTrait, Condition, sea_query::Query};
+
+use crate::{
+    database::{sea_orm_active_enums::UserRole, ticket_assignees, tickets},
+    middleware::guard::AuthUser,
+    utils::api_error::ApiError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TicketAction {
+    Read,
+    Edit,
+    Assign,
+    Delete,
+}
+
+impl fmt::Display for TicketAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self {
+            TicketAction::Read => "read",
+            TicketAction::Edit => "edit",
+            TicketAction::Assign => "assign",
+            TicketAction::Delete => "delete",
+        };
+        write!(f, "{action}")
+    }
+}
+
+fn is_owner(user: &AuthUser, ticket: &tickets::Model) -> bool {
+    ticket.user_id == Some(user.id)
+}
+
+/// Decides whether `user` may perform `action` on `ticket`, `assignees` being
+/// the ids of the users assigned to it
+///
+/// Admins may do anything. Live tickets are readable by every authenticated
+/// user, soft-deleted tickets are readable by the reporter and assignees.
+/// Assignees may edit, deleting and assigning is reserved to the reporter,
+/// and read-only users can never mutate
+pub fn can(
+    user: &AuthUser,
+    ticket: &tickets::Model,
+    assignees: &[i32],
+    action: TicketAction,
+) -> bool {
+    let is_assignee = assignees.contains(&user.id);
+
+    match (user.role, action) {
+        (UserRole::Admin, _) => true,
+        (_, TicketAction::Read) => {
+            ticket.deleted_at.is_none() || is_owner(user, ticket) || is_assignee
+        }
+        (UserRole::ReadOnly, _) => false,
+        (UserRole::Member, TicketAction::Edit) => is_owner(user, ticket) || is_assignee,
+        (UserRole::Member, TicketAction::Assign | TicketAction::Delete) => is_owner(user, ticket),
+    }
+}
+
+pub fn authorize(
+    user: &AuthUser,
+    ticket: &tickets::Model,
+    assignees: &[i32],
+    action: TicketAction,
+) -> Result<(), ApiError> {
+    if can(user, ticket, assignees, action) {
+        Ok(())
+    } else {
+        tracing::warn!(
+            "User {:?} denied {} on ticket {:?}",
+            user.id,
+            action,
+            ticket.id
+        );
+        Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            format!("You are not allowed to {action} this ticket"),
+        ))
+    }
+}
+
+/// Query condition matching the tickets `user` is allowed to read
+pub fn visible_to(user: &AuthUser, include_deleted: bool) -> Condition {
+    if include_deleted && user.role == UserRole::Admin {
+        Condition::all()
+    } else if include_deleted {
+        Condition::any()
+            .add(tickets::Column::DeletedAt.is_null())
+            .add(tickets::Column::UserId.eq(user.id))
+            .add(assigned_to(user.id))
+    } else {
+        Condition::all().add(tickets::Column::DeletedAt.is_null())
+    }
+}
+
+/// Query condition matching the tickets assigned to `user_id`
+pub fn assigned_to(user_id: i32) -> Condition {
+    Condition::all().add(
+        tickets::Column::Id.in_subquery(
+            Query::select()
+                .column(ticket_assignees::Column::TicketId)
+                .from(ticket_assignees::Entity)
+                .and_where(ticket_assignees::Column::UserId.eq(user_id))
+                .to_owned(),
+        ),
+    )
+}
@@ -0,0 +1,143 @@
+use axum::{Extension, Json, extract::State, http::StatusCode};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set, TransactionTrait, prelude::Expr,
+};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::{
+    database::{
+        sea_orm_active_enums::UserRole,
+        sessions::{self, Entity as Sessions},
+        users::{self, Entity as Users},
+    },
+    middleware::guard::AuthUser,
+    utils::{api_error::ApiError, extract::Path, validation::ValidatedJson},
+};
+
+#[derive(Serialize, Debug)]
+pub struct ResponseAdminUser {
+    id: i32,
+    username: String,
+    role: UserRole,
+}
+
+impl From<users::Model> for ResponseAdminUser {
+    fn from(user: users::Model) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            role: user.role,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RequestRole {
+    role: UserRole,
+}
+
+pub async fn admin_list_users(
+    State(db_conn): State<DatabaseConnection>,
+) -> Result<Json<Vec<ResponseAdminUser>>, ApiError> {
+    let users = Users::find()
+        .order_by_asc(users::Column::Id)
+        .all(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error fetching users")
+        })?
+        .into_iter()
+        .map(ResponseAdminUser::from)
+        .collect();
+
+    Ok(Json(users))
+}
+
+pub async fn admin_grant_role(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(admin): Extension<AuthUser>,
+    Path(user_id): Path<i32>,
+    ValidatedJson(req_role): ValidatedJson<RequestRole>,
+) -> Result<Json<ResponseAdminUser>, ApiError> {
+    set_role(&db_conn, &admin, user_id, req_role.role).await
+}
+
+/// Revoking drops the user back to the default `member` role
+pub async fn admin_revoke_role(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(admin): Extension<AuthUser>,
+    Path(user_id): Path<i32>,
+) -> Result<Json<ResponseAdminUser>, ApiError> {
+    set_role(&db_conn, &admin, user_id, UserRole::Member).await
+}
+
+async fn set_role(
+    db_conn: &DatabaseConnection,
+    admin: &AuthUser,
+    user_id: i32,
+    role: UserRole,
+) -> Result<Json<ResponseAdminUser>, ApiError> {
+    // Stops the last admin from locking everyone out
+    if admin.id == user_id {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "Admins cannot change their own role",
+        ));
+    }
+
+    let user = Users::find_by_id(user_id)
+        .one(db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch user")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "Requested user not found"))?;
+
+    let role_changed = user.role != role;
+    let mut user = user.into_active_model();
+    user.role = Set(role);
+
+    let txn = db_conn.begin().await?;
+
+    let user = user.update(&txn).await.map_err(|err| {
+        tracing::error!("Error: {:?}", err);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to update user")
+    })?;
+
+    // Access tokens carry the role, so the user's sessions end with the old
+    // role and the next login picks up the new one
+    if role_changed {
+        Sessions::update_many()
+            .col_expr(
+                sessions::Column::RevokedAt,
+                Expr::value(Utc::now().fixed_offset()),
+            )
+            .col_expr(
+                sessions::Column::AccessJti,
+                Expr::value(Option::<String>::None),
+            )
+            .filter(sessions::Column::UserId.eq(user.id))
+            .filter(sessions::Column::RevokedAt.is_null())
+            .exec(&txn)
+            .await
+            .map_err(|err| {
+                tracing::error!("Error: {:?}", err);
+                ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to update user")
+            })?;
+    }
+
+    txn.commit().await?;
+
+    tracing::info!(
+        "User {:?} set role of {:?} to {:?}",
+        admin.id,
+        user.username,
+        role
+    );
+    Ok(Json(ResponseAdminUser::from(user)))
+}
@@ -1,15 +1,85 @@
-use axum::extract::State;
+use std::time::{Duration, Instant};
+
+use axum::{Json, extract::State, http::StatusCode};
 use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr};
+use serde::Serialize;
+
+use super::AppState;
+
+#[derive(Serialize)]
+pub struct ResponseHealth {
+    status: &'static str,
+    version: &'static str,
+    uptime_seconds: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    database: Option<ResponseDatabaseHealth>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseDatabaseHealth {
+    status: &'static str,
+    latency_ms: Option<f64>,
+}
+
+/// How long the database gets to answer before it is reported down
+const DB_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
 
-use crate::utils::api_error::ApiError;
+/// Round-trips a trivial query and returns how long it took, giving up after
+/// `DB_CHECK_TIMEOUT` so a hung database does not hold the probe open
+async fn db_check(db_conn: &DatabaseConnection) -> Result<Duration, DbErr> {
+    let start = Instant::now();
+    tokio::time::timeout(DB_CHECK_TIMEOUT, db_conn.execute_unprepared("SELECT 1"))
+        .await
+        .map_err(|_| DbErr::Custom(format!("no answer within {DB_CHECK_TIMEOUT:?}")))??;
+    Ok(start.elapsed())
+}
+
+async fn database_health(db_conn: &DatabaseConnection) -> ResponseDatabaseHealth {
+    match db_check(db_conn).await {
+        Ok(latency) => ResponseDatabaseHealth {
+            status: "up",
+            latency_ms: Some(latency.as_secs_f64() * 1000.0),
+        },
+        Err(err) => {
+            tracing::error!("Database health check failed: {:?}", err);
+            ResponseDatabaseHealth {
+                status: "down",
+                latency_ms: None,
+            }
+        }
+    }
+}
+
+fn health_response(
+    app_state: &AppState,
+    database: Option<ResponseDatabaseHealth>,
+) -> (StatusCode, Json<ResponseHealth>) {
+    let healthy = database.as_ref().is_none_or(|db| db.status == "up");
+    let (code, status) = if healthy {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    };
+
+    (
+        code,
+        Json(ResponseHealth {
+            status,
+            version: env!("CARGO_PKG_VERSION"),
+            uptime_seconds: app_state.started_at.elapsed().as_secs(),
+            database,
+        }),
+    )
+}
 
-#[allow(dead_code)]
-async fn db_check(db_conn: DatabaseConnection) -> Result<(), DbErr> {
-    db_conn.execute_unprepared("SELECT 1").await?;
-    Ok(())
+/// Full status report, answers 503 when the database is unreachable. Also
+/// serves as the readiness probe.
+pub async fn health_check(State(app_state): State<AppState>) -> (StatusCode, Json<ResponseHealth>) {
+    let database = database_health(&app_state.db_conn).await;
+    health_response(&app_state, Some(database))
 }
 
-#[allow(dead_code)]
-pub async fn health_check(State(_db_conn): State<DatabaseConnection>) -> Result<(), ApiError> {
-    todo!()
+/// Liveness probe, only reports that the process is serving requests
+pub async fn livez(State(app_state): State<AppState>) -> (StatusCode, Json<ResponseHealth>) {
+    health_response(&app_state, None)
 }
@@ -0,0 +1,10 @@
+use axum::{Json, extract::State};
+use jsonwebtoken::jwk::JwkSet;
+use std::sync::Arc;
+
+use crate::utils::jwt_keys::JwtKeys;
+
+/// Public signing keys, HS256 secrets are never listed
+pub async fn jwks(State(jwt_keys): State<Arc<JwtKeys>>) -> Json<JwkSet> {
+    Json(jwt_keys.jwks())
+}
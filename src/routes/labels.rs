@@ -0,0 +1,165 @@
+use axum::{Json, extract::State, http::StatusCode};
+use sea_orm::{
+    ActiveModelTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel, QueryOrder, Set,
+    SqlErr,
+};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::{
+    database::labels::{self, Entity as Labels},
+    utils::{
+        api_error::ApiError,
+        extract::Path,
+        validation::{COLOUR_PATTERN, LABEL_NAME_PATTERN, ValidatedJson, not_blank},
+    },
+};
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RequestLabel {
+    #[validate(
+        custom(function = "not_blank"),
+        length(min = 1, max = 64, message = "must be between 1 and 64 characters"),
+        regex(
+            path = *LABEL_NAME_PATTERN,
+            message = "may only contain letters, digits, spaces, `_`, `.`, `:`, `/` and `-`"
+        )
+    )]
+    name: String,
+    #[validate(regex(path = *COLOUR_PATTERN, message = "must be a hex colour like #1f883d"))]
+    colour: String,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RequestLabelUpdate {
+    #[validate(
+        custom(function = "not_blank"),
+        length(min = 1, max = 64, message = "must be between 1 and 64 characters"),
+        regex(
+            path = *LABEL_NAME_PATTERN,
+            message = "may only contain letters, digits, spaces, `_`, `.`, `:`, `/` and `-`"
+        )
+    )]
+    name: Option<String>,
+    #[validate(regex(path = *COLOUR_PATTERN, message = "must be a hex colour like #1f883d"))]
+    colour: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseLabel {
+    id: i32,
+    name: String,
+    colour: String,
+}
+
+impl From<labels::Model> for ResponseLabel {
+    fn from(label: labels::Model) -> Self {
+        Self {
+            id: label.id,
+            name: label.name,
+            colour: label.colour,
+        }
+    }
+}
+
+fn save_error(err: DbErr) -> ApiError {
+    match err.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => ApiError::new(
+            StatusCode::CONFLICT,
+            "A label with this name already exists",
+        )
+        .with_code("label_exists"),
+        _ => {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to save label")
+        }
+    }
+}
+
+async fn find_label(
+    db_conn: &DatabaseConnection,
+    label_id: i32,
+) -> Result<labels::Model, ApiError> {
+    Labels::find_by_id(label_id)
+        .one(db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch label")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No label found"))
+}
+
+pub async fn label_list(
+    State(db_conn): State<DatabaseConnection>,
+) -> Result<Json<Vec<ResponseLabel>>, ApiError> {
+    let labels = Labels::find()
+        .order_by_asc(labels::Column::Name)
+        .all(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error fetching labels")
+        })?
+        .into_iter()
+        .map(ResponseLabel::from)
+        .collect();
+
+    Ok(Json(labels))
+}
+
+pub async fn label_create(
+    State(db_conn): State<DatabaseConnection>,
+    ValidatedJson(req_label): ValidatedJson<RequestLabel>,
+) -> Result<(StatusCode, Json<ResponseLabel>), ApiError> {
+    let label = labels::ActiveModel {
+        name: Set(req_label.name),
+        colour: Set(req_label.colour.to_lowercase()),
+        ..Default::default()
+    }
+    .insert(&db_conn)
+    .await
+    .map_err(save_error)?;
+
+    tracing::info!("Label {:?} created", label.id);
+    Ok((StatusCode::CREATED, Json(ResponseLabel::from(label))))
+}
+
+pub async fn label_update(
+    State(db_conn): State<DatabaseConnection>,
+    Path(label_id): Path<i32>,
+    ValidatedJson(req_label): ValidatedJson<RequestLabelUpdate>,
+) -> Result<Json<ResponseLabel>, ApiError> {
+    let mut label = find_label(&db_conn, label_id).await?.into_active_model();
+
+    if let Some(name) = req_label.name {
+        label.name = Set(name);
+    }
+
+    if let Some(colour) = req_label.colour {
+        label.colour = Set(colour.to_lowercase());
+    }
+
+    let label = label.update(&db_conn).await.map_err(save_error)?;
+
+    Ok(Json(ResponseLabel::from(label)))
+}
+
+/// Deleting a label also takes it off every ticket carrying it
+pub async fn label_delete(
+    State(db_conn): State<DatabaseConnection>,
+    Path(label_id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    let label = find_label(&db_conn, label_id).await?;
+
+    Labels::delete_by_id(label.id)
+        .exec(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to delete label")
+        })?;
+
+    tracing::info!("Label {:?} deleted", label.id);
+    Ok(StatusCode::NO_CONTENT)
+}
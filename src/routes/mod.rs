@@ -1,38 +1,108 @@
+mod admin;
 mod health_check;
+mod jwks;
+mod labels;
+mod projects;
+mod session;
 mod ticket;
+mod ticket_assignees;
+mod ticket_comments;
+mod ticket_delete;
 mod ticket_get;
+mod ticket_history;
+mod ticket_labels;
+mod ticket_query;
+mod ticket_search;
+mod ticket_transition;
 mod ticket_update_atomic;
 mod ticket_update_partial;
 mod user;
 mod user_auth;
+mod views;
 
-use health_check::health_check;
-use ticket::ticket_create;
-use ticket_get::{ticket_get_all, ticket_get_single};
+use admin::{admin_grant_role, admin_list_users, admin_revoke_role};
+use health_check::{health_check, livez};
+use jwks::jwks;
+use labels::{label_create, label_delete, label_list, label_update};
+use projects::{
+    project_create, project_get, project_list, project_member_add, project_member_remove,
+};
+use session::{session_list, session_revoke, token_refresh};
+use ticket::{ticket_create, ticket_create_in_project};
+use ticket_assignees::{assignee_add, assignee_remove};
+use ticket_comments::{comment_create, comment_delete, comment_list, comment_update};
+use ticket_delete::{ticket_delete, ticket_purge, ticket_restore};
+use ticket_get::{
+    ticket_get_all, ticket_get_assigned, ticket_get_by_key, ticket_get_project, ticket_get_single,
+};
+use ticket_history::ticket_history;
+use ticket_labels::{ticket_label_add, ticket_label_remove};
+use ticket_search::ticket_search;
+use ticket_transition::ticket_transition;
 use ticket_update_atomic::ticket_update_atomic;
 use ticket_update_partial::ticket_update_partial;
 use user::create_user;
 use user_auth::{login, logout};
+use views::{
+    view_create, view_default_get, view_default_set, view_delete, view_get, view_list,
+    view_tickets, view_update,
+};
 
 use axum::{
     Router,
     extract::FromRef,
-    http::Method,
+    http::{Method, StatusCode, header},
     middleware,
-    routing::{get, patch, post, put},
+    routing::{delete, get, patch, post, put},
 };
 use sea_orm::DatabaseConnection;
-use tower_http::cors::{Any, CorsLayer};
+use std::{sync::Arc, time::Instant};
+use tower_http::cors::{AllowOrigin, CorsLayer};
 
-use crate::middleware::guard::guard;
+use crate::config::Config;
+use crate::database::sea_orm_active_enums::UserRole;
+use crate::middleware::{
+    guard::guard,
+    request_context::{REQUEST_ID_HEADER, request_context},
+    require_role::require_role,
+};
+use crate::utils::api_error::ApiError;
+use crate::utils::jwt_keys::JwtKeys;
+use crate::utils::prefer::PREFERENCE_APPLIED_HEADER;
 
 #[derive(Clone, FromRef)]
 pub struct AppState {
     pub db_conn: DatabaseConnection,
+    pub config: Arc<Config>,
+    pub jwt_keys: Arc<JwtKeys>,
+    pub started_at: Instant,
 }
 
-pub fn routes(db_conn: DatabaseConnection) -> Router {
-    let app_state = AppState { db_conn };
+pub fn routes(db_conn: DatabaseConnection, config: Config, jwt_keys: JwtKeys) -> Router {
+    let allow_origin = if config
+        .cors
+        .allowed_origins
+        .iter()
+        .any(|origin| origin == "*")
+    {
+        AllowOrigin::any()
+    } else {
+        // Origins are validated when the config is loaded
+        AllowOrigin::list(
+            config
+                .cors
+                .allowed_origins
+                .iter()
+                .filter_map(|origin| origin.parse().ok()),
+        )
+    };
+
+    let app_state = AppState {
+        db_conn,
+        config: Arc::new(config),
+        jwt_keys: Arc::new(jwt_keys),
+        started_at: Instant::now(),
+    };
 
     let cors = CorsLayer::new()
         .allow_methods([
@@ -40,9 +110,15 @@ pub fn routes(db_conn: DatabaseConnection) -> Router {
             Method::POST,
             Method::PATCH,
             Method::PUT,
-            // Method::DELETE,
+            Method::DELETE,
         ])
-        .allow_origin(Any);
+        .allow_origin(allow_origin)
+        .expose_headers([
+            REQUEST_ID_HEADER.clone(),
+            header::ETAG,
+            header::LOCATION,
+            PREFERENCE_APPLIED_HEADER.clone(),
+        ]);
 
     // FIX: nesting routes
     // let user_routes = Router::new()
@@ -53,19 +129,100 @@ pub fn routes(db_conn: DatabaseConnection) -> Router {
 
     // .nest("/users", user_routes)
     Router::new()
+        // Admin routes
+        .route("/admin/users", get(admin_list_users))
+        .route("/admin/users/{id}/role", put(admin_grant_role))
+        .route("/admin/users/{id}/role", delete(admin_revoke_role))
+        .route("/labels/{id}", delete(label_delete))
+        .route("/projects", post(project_create))
+        .route("/projects/{key}/members", post(project_member_add))
+        .route(
+            "/projects/{key}/members/{user_id}",
+            delete(project_member_remove),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            UserRole::Admin,
+            require_role,
+        ))
+        // Ticket routes that modify data
+        .route("/tickets", post(ticket_create))
+        .route("/tickets/{id}", put(ticket_update_atomic))
+        .route("/tickets/{id}", patch(ticket_update_partial))
+        .route("/tickets/{id}", delete(ticket_delete))
+        .route("/tickets/{id}/restore", post(ticket_restore))
+        .route("/tickets/{id}/purge", delete(ticket_purge))
+        .route("/tickets/{id}/transition", post(ticket_transition))
+        .route("/tickets/{id}/assignees", post(assignee_add))
+        .route("/tickets/{id}/assignees/{user_id}", delete(assignee_remove))
+        .route("/tickets/{id}/labels", post(ticket_label_add))
+        .route(
+            "/tickets/{id}/labels/{label_id}",
+            delete(ticket_label_remove),
+        )
+        .route("/tickets/{id}/comments", post(comment_create))
+        .route("/tickets/{id}/comments/{comment_id}", patch(comment_update))
+        .route(
+            "/tickets/{id}/comments/{comment_id}",
+            delete(comment_delete),
+        )
+        .route("/projects/{key}/tickets", post(ticket_create_in_project))
+        .route("/labels", post(label_create))
+        .route("/labels/{id}", patch(label_update))
+        .route_layer(middleware::from_fn_with_state(
+            UserRole::Member,
+            require_role,
+        ))
         // Auth routes
         .route("/logout", post(logout))
-        .route_layer(middleware::from_fn_with_state(app_state.clone(), guard))
-        .route("/create_user", post(create_user))
-        .route("/login", post(login))
+        .route("/sessions", get(session_list))
+        .route("/sessions/{id}", delete(session_revoke))
         // Ticket routes
-        .route("/tickets", post(ticket_create))
         .route("/tickets", get(ticket_get_all))
+        .route("/tickets/assigned", get(ticket_get_assigned))
+        .route("/tickets/search", get(ticket_search))
         .route("/tickets/{id}", get(ticket_get_single))
-        .route("/tickets/{id}", put(ticket_update_atomic))
-        .route("/tickets/{id}", patch(ticket_update_partial))
+        .route("/tickets/{id}/comments", get(comment_list))
+        .route("/tickets/{id}/history", get(ticket_history))
+        .route("/labels", get(label_list))
+        .route("/projects", get(project_list))
+        .route("/projects/{key}", get(project_get))
+        .route("/projects/{key}/tickets", get(ticket_get_project))
+        .route("/projects/{key}/tickets/{number}", get(ticket_get_by_key))
+        // Saved view routes
+        .route("/views", get(view_list).post(view_create))
+        .route(
+            "/views/default",
+            get(view_default_get).put(view_default_set),
+        )
+        .route(
+            "/views/{id}",
+            get(view_get).patch(view_update).delete(view_delete),
+        )
+        .route("/views/{id}/tickets", get(view_tickets))
+        // Everything above requires a logged in user
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), guard))
+        .route("/create_user", post(create_user))
+        .route("/login", post(login))
+        .route("/token/refresh", post(token_refresh))
+        .route("/.well-known/jwks.json", get(jwks))
         // Health check routes
         .route("/health_check", get(health_check))
+        .route("/livez", get(livez))
+        .route("/readyz", get(health_check))
+        .fallback(not_found)
+        .method_not_allowed_fallback(method_not_allowed)
         .with_state(app_state)
         .layer(cors)
+        .layer(middleware::from_fn(request_context))
+}
+
+async fn method_not_allowed() -> ApiError {
+    ApiError::new(
+        StatusCode::METHOD_NOT_ALLOWED,
+        "Method not allowed on this path",
+    )
+}
+
+async fn not_found() -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, "No route matches this path").with_code("route_not_found")
 }
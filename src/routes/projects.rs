@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+
+use axum::{Json, extract::State, http::StatusCode};
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+    SqlErr, sea_query::OnConflict,
+};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::{
+    database::{
+        project_members::{self, Entity as ProjectMembers},
+        projects::{self, Entity as Projects},
+        users::{self, Entity as Users},
+    },
+    utils::{
+        api_error::ApiError,
+        extract::Path,
+        validation::{PROJECT_KEY_PATTERN, ValidatedJson, not_blank},
+    },
+};
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RequestProject {
+    #[validate(
+        length(min = 2, max = 10, message = "must be between 2 and 10 characters"),
+        regex(
+            path = *PROJECT_KEY_PATTERN,
+            message = "must be an uppercase letter followed by uppercase letters or digits"
+        )
+    )]
+    key: String,
+    #[validate(
+        custom(function = "not_blank"),
+        length(max = 255, message = "must be at most 255 characters")
+    )]
+    name: String,
+    #[validate(length(max = 10000, message = "must be at most 10000 characters"))]
+    description: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RequestProjectMember {
+    #[validate(range(min = 1, message = "must be a user id"))]
+    user_id: i32,
+}
+
+#[derive(Serialize)]
+pub struct ResponseProject {
+    id: i32,
+    key: String,
+    name: String,
+    description: Option<String>,
+    created_at: DateTime<FixedOffset>,
+    members: Vec<i32>,
+}
+
+impl ResponseProject {
+    fn new(project: projects::Model, members: Vec<i32>) -> Self {
+        Self {
+            id: project.id,
+            key: project.key,
+            name: project.name,
+            description: project.description,
+            created_at: project.created_at,
+            members,
+        }
+    }
+}
+
+/// Project by its key, keys are matched case-insensitively
+pub async fn find_project(
+    db_conn: &DatabaseConnection,
+    key: &str,
+) -> Result<projects::Model, ApiError> {
+    Projects::find()
+        .filter(projects::Column::Key.eq(key.to_uppercase()))
+        .one(db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch project")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No project found"))
+}
+
+/// Ids of a project's members, in joining order
+pub async fn member_ids(
+    db_conn: &DatabaseConnection,
+    project_id: i32,
+) -> Result<Vec<i32>, ApiError> {
+    Ok(members_by_project(db_conn, &[project_id])
+        .await?
+        .remove(&project_id)
+        .unwrap_or_default())
+}
+
+async fn members_by_project(
+    db_conn: &DatabaseConnection,
+    project_ids: &[i32],
+) -> Result<HashMap<i32, Vec<i32>>, ApiError> {
+    let members = ProjectMembers::find()
+        .filter(project_members::Column::ProjectId.is_in(project_ids.iter().copied()))
+        .order_by_asc(project_members::Column::JoinedAt)
+        .order_by_asc(project_members::Column::UserId)
+        .all(db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to fetch project members",
+            )
+        })?;
+
+    let mut by_project: HashMap<i32, Vec<i32>> = HashMap::new();
+    for member in members {
+        by_project
+            .entry(member.project_id)
+            .or_default()
+            .push(member.user_id);
+    }
+    Ok(by_project)
+}
+
+/// Keys of the given projects, fetched in one query
+pub async fn project_keys(
+    db_conn: &DatabaseConnection,
+    project_ids: &[i32],
+) -> Result<HashMap<i32, String>, ApiError> {
+    if project_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let projects = Projects::find()
+        .filter(projects::Column::Id.is_in(project_ids.iter().copied()))
+        .all(db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to fetch projects",
+            )
+        })?;
+
+    Ok(projects
+        .into_iter()
+        .map(|project| (project.id, project.key))
+        .collect())
+}
+
+pub async fn project_list(
+    State(db_conn): State<DatabaseConnection>,
+) -> Result<Json<Vec<ResponseProject>>, ApiError> {
+    let projects = Projects::find()
+        .order_by_asc(projects::Column::Key)
+        .all(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error fetching projects")
+        })?;
+
+    let project_ids: Vec<i32> = projects.iter().map(|project| project.id).collect();
+    let mut members = members_by_project(&db_conn, &project_ids).await?;
+
+    let projects = projects
+        .into_iter()
+        .map(|project| {
+            let project_members = members.remove(&project.id).unwrap_or_default();
+            ResponseProject::new(project, project_members)
+        })
+        .collect();
+
+    Ok(Json(projects))
+}
+
+pub async fn project_get(
+    State(db_conn): State<DatabaseConnection>,
+    Path(key): Path<String>,
+) -> Result<Json<ResponseProject>, ApiError> {
+    let project = find_project(&db_conn, &key).await?;
+    let members = member_ids(&db_conn, project.id).await?;
+
+    Ok(Json(ResponseProject::new(project, members)))
+}
+
+pub async fn project_create(
+    State(db_conn): State<DatabaseConnection>,
+    ValidatedJson(req_project): ValidatedJson<RequestProject>,
+) -> Result<(StatusCode, Json<ResponseProject>), ApiError> {
+    let project = projects::ActiveModel {
+        key: Set(req_project.key),
+        name: Set(req_project.name),
+        description: Set(req_project.description),
+        created_at: Set(Utc::now().fixed_offset()),
+        ..Default::default()
+    }
+    .insert(&db_conn)
+    .await
+    .map_err(|err| match err.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            ApiError::new(StatusCode::CONFLICT, "Project key is already taken")
+                .with_code("project_key_taken")
+        }
+        _ => {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to create project",
+            )
+        }
+    })?;
+
+    tracing::info!("Project {:?} created", project.key);
+    Ok((
+        StatusCode::CREATED,
+        Json(ResponseProject::new(project, Vec::new())),
+    ))
+}
+
+pub async fn project_member_add(
+    State(db_conn): State<DatabaseConnection>,
+    Path(key): Path<String>,
+    ValidatedJson(req_member): ValidatedJson<RequestProjectMember>,
+) -> Result<StatusCode, ApiError> {
+    let project = find_project(&db_conn, &key).await?;
+
+    let member = Users::find_by_id(req_member.user_id)
+        .filter(users::Column::DeletedAt.is_null())
+        .one(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch user")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No user found"))?;
+
+    // Adding a member twice is a no-op
+    ProjectMembers::insert(project_members::ActiveModel {
+        project_id: Set(project.id),
+        user_id: Set(member.id),
+        joined_at: Set(Utc::now().fixed_offset()),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            project_members::Column::ProjectId,
+            project_members::Column::UserId,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .do_nothing()
+    .exec(&db_conn)
+    .await
+    .map_err(|err| {
+        tracing::error!("Error: {:?}", err);
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to add project member",
+        )
+    })?;
+
+    tracing::info!("User {:?} added to project {:?}", member.id, project.key);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn project_member_remove(
+    State(db_conn): State<DatabaseConnection>,
+    Path((key, user_id)): Path<(String, i32)>,
+) -> Result<StatusCode, ApiError> {
+    let project = find_project(&db_conn, &key).await?;
+
+    let removed = ProjectMembers::delete_by_id((project.id, user_id))
+        .exec(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to remove project member",
+            )
+        })?;
+
+    if removed.rows_affected == 0 {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "User is not a member of this project",
+        ));
+    }
+
+    tracing::info!("User {:?} removed from project {:?}", user_id, project.key);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,98 @@
+use axum::{Extension, Json, extract::State, http::StatusCode};
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
+
+use crate::{
+    config::Config,
+    database::sessions::{self, Entity as Sessions},
+    middleware::guard::AuthUser,
+    utils::{
+        api_error::ApiError,
+        extract::Path,
+        jwt_keys::JwtKeys,
+        session::{TokenPair, revoke_session, rotate_refresh_token},
+        validation::ValidatedJson,
+    },
+};
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RequestRefresh {
+    #[validate(length(min = 1, max = 128, message = "must be between 1 and 128 characters"))]
+    refresh_token: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ResponseSession {
+    id: i32,
+    user_agent: Option<String>,
+    created_at: DateTime<FixedOffset>,
+    last_used_at: DateTime<FixedOffset>,
+    expires_at: DateTime<FixedOffset>,
+    /// Whether this is the session making the request
+    current: bool,
+}
+
+pub async fn token_refresh(
+    State(db_conn): State<DatabaseConnection>,
+    State(config): State<Arc<Config>>,
+    State(jwt_keys): State<Arc<JwtKeys>>,
+    ValidatedJson(req_refresh): ValidatedJson<RequestRefresh>,
+) -> Result<Json<TokenPair>, ApiError> {
+    let tokens =
+        rotate_refresh_token(&db_conn, &config.jwt, &jwt_keys, &req_refresh.refresh_token).await?;
+    Ok(Json(tokens))
+}
+
+pub async fn session_list(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Vec<ResponseSession>>, ApiError> {
+    let sessions = Sessions::find()
+        .filter(sessions::Column::UserId.eq(user.id))
+        .filter(sessions::Column::RevokedAt.is_null())
+        .filter(sessions::Column::ExpiresAt.gt(Utc::now().fixed_offset()))
+        .order_by_desc(sessions::Column::LastUsedAt)
+        .all(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error fetching sessions")
+        })?
+        .into_iter()
+        .map(|session| ResponseSession {
+            current: session.id == user.session_id,
+            id: session.id,
+            user_agent: session.user_agent,
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+            expires_at: session.expires_at,
+        })
+        .collect();
+
+    Ok(Json(sessions))
+}
+
+pub async fn session_revoke(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path(session_id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    Sessions::find_by_id(session_id)
+        .filter(sessions::Column::UserId.eq(user.id))
+        .filter(sessions::Column::RevokedAt.is_null())
+        .one(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch session")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "Session not found"))?;
+
+    revoke_session(&db_conn, session_id).await?;
+
+    tracing::info!("User {:?} revoked session {:?}", user.id, session_id);
+    Ok(StatusCode::NO_CONTENT)
+}
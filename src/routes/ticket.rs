@@ -1,64 +1,161 @@
-use axum::{Json, extract::State, http::StatusCode};
-use axum_extra::{
-    TypedHeader,
-    headers::{Authorization, authorization::Bearer},
+use axum::{
+    Extension,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::Response,
+};
+use axum_extra::TypedHeader;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+    TransactionTrait, prelude::Expr,
 };
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
 use serde::Deserialize;
+use serde_json::{Value, json};
+use validator::Validate;
 
-use crate::database::users::Entity as Users;
+use super::{
+    projects::{find_project, member_ids},
+    ticket_get::ResponseTicket,
+    ticket_history::record_event,
+};
 use crate::{
-    database::{tickets, users},
-    utils::api_error::ApiError,
+    database::{
+        projects::{self, Entity as Projects},
+        sea_orm_active_enums::TicketPriority,
+        tickets,
+    },
+    middleware::guard::AuthUser,
+    policy::project::{self as project_policy, ProjectAction},
+    utils::{
+        api_error::ApiError,
+        extract::Path,
+        precondition::ticket_etag,
+        prefer::written,
+        validation::{ValidatedJson, not_blank},
+    },
 };
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Validate)]
 pub struct RequestTicket {
+    #[validate(
+        custom(function = "not_blank"),
+        length(max = 255, message = "must be at most 255 characters")
+    )]
     title: String,
+    #[validate(length(max = 10000, message = "must be at most 10000 characters"))]
     description: Option<String>,
-    priority: Option<String>,
+    priority: Option<TicketPriority>,
+}
+
+impl RequestTicket {
+    fn into_active_model(self, user: &AuthUser) -> tickets::ActiveModel {
+        tickets::ActiveModel {
+            title: Set(self.title),
+            description: Set(self.description),
+            priority: Set(self.priority),
+            user_id: Set(Some(user.id)),
+            created_at: Set(Utc::now().fixed_offset()),
+            ..Default::default()
+        }
+    }
 }
 
 pub async fn ticket_create(
     State(db_conn): State<DatabaseConnection>,
-    auth: TypedHeader<Authorization<Bearer>>,
-    Json(req_ticket): Json<RequestTicket>,
-) -> Result<(), ApiError> {
+    Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
+    ValidatedJson(req_ticket): ValidatedJson<RequestTicket>,
+) -> Result<Response, ApiError> {
     tracing::info!("Attempting to create ticket...");
-    let header_token = auth.token();
 
-    let user = if let Some(user) = Users::find()
-        .filter(users::Column::Token.eq(header_token))
-        .one(&db_conn)
-        .await
-        .map_err(|err| {
-            tracing::error!("Error: {:?}", err);
-            ApiError::new(StatusCode::UNAUTHORIZED, "Log in to create a ticket")
-        })? {
-        user
-    } else {
-        return Err(ApiError::new(
-            StatusCode::UNAUTHORIZED,
-            "Unauthorized, please log in",
-        ));
-    };
+    let ticket = req_ticket.into_active_model(&user);
+
+    let txn = db_conn.begin().await?;
+    let ticket = ticket.insert(&txn).await?;
+    record_event(
+        &txn,
+        ticket.id,
+        user.id,
+        "created",
+        Value::Null,
+        json!(ticket.title),
+    )
+    .await?;
+    txn.commit().await?;
+
+    tracing::info!("New ticket created by User {:?}", user.id);
+    let location = [(header::LOCATION, format!("/tickets/{}", ticket.id))];
+    let etag = TypedHeader(ticket_etag(ticket.version));
+    Ok(written(
+        &headers,
+        StatusCode::CREATED,
+        (location, etag),
+        ResponseTicket::new(ticket, Vec::new(), Vec::new(), None),
+    ))
+}
+
+/// Files a ticket in a project, numbering it after the project's last ticket
+pub async fn ticket_create_in_project(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path(key): Path<String>,
+    headers: HeaderMap,
+    ValidatedJson(req_ticket): ValidatedJson<RequestTicket>,
+) -> Result<Response, ApiError> {
+    let project = find_project(&db_conn, &key).await?;
+    let members = member_ids(&db_conn, project.id).await?;
 
-    let ticket = tickets::ActiveModel {
-        title: Set(req_ticket.title),
-        description: Set(req_ticket.description),
-        priority: Set(req_ticket.priority),
-        user_id: Set(Some(user.id)),
-        ..Default::default()
-    };
+    project_policy::authorize(&user, &project, &members, ProjectAction::CreateTicket)?;
 
-    ticket.save(&db_conn).await.map_err(|err| {
-        tracing::error!("Error: {:?}", err);
-        ApiError::new(
-            StatusCode::UNAUTHORIZED,
-            "Database error, cannot save ticket",
+    let txn = db_conn.begin().await?;
+
+    // Bumping the counter locks the project row until commit, so concurrent
+    // creates never hand out the same number
+    let number = Projects::update_many()
+        .col_expr(
+            projects::Column::NextNumber,
+            Expr::col(projects::Column::NextNumber).add(1),
         )
-    })?;
+        .filter(projects::Column::Id.eq(project.id))
+        .exec_with_returning(&txn)
+        .await?
+        .into_iter()
+        .next()
+        .map(|project| project.next_number - 1)
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No project found"))?;
+
+    let mut ticket = req_ticket.into_active_model(&user);
+    ticket.project_id = Set(Some(project.id));
+    ticket.number = Set(Some(number));
+    let ticket = ticket.insert(&txn).await?;
+    record_event(
+        &txn,
+        ticket.id,
+        user.id,
+        "created",
+        Value::Null,
+        json!(ticket.title),
+    )
+    .await?;
+
+    txn.commit().await?;
 
-    tracing::info!("New ticket created by User {:?}", user.username);
-    Ok(())
+    tracing::info!(
+        "Ticket {}-{} created by User {:?}",
+        project.key,
+        number,
+        user.id
+    );
+    let location = [(
+        header::LOCATION,
+        format!("/projects/{}/tickets/{}", project.key, number),
+    )];
+    let etag = TypedHeader(ticket_etag(ticket.version));
+    Ok(written(
+        &headers,
+        StatusCode::CREATED,
+        (location, etag),
+        ResponseTicket::new(ticket, Vec::new(), Vec::new(), Some(project.key)),
+    ))
 }
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use axum::{Extension, extract::State, http::StatusCode};
+use chrono::Utc;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+    TryInsertResult, sea_query::OnConflict,
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use validator::Validate;
+
+use super::ticket_history::{record_event, touch_ticket};
+use crate::{
+    database::{
+        sea_orm_active_enums::UserRole,
+        ticket_assignees::{self, Entity as TicketAssignees},
+        tickets::{self, Entity as Tickets},
+        users::{self, Entity as Users},
+    },
+    middleware::guard::AuthUser,
+    policy::ticket::{self as ticket_policy, TicketAction},
+    utils::{api_error::ApiError, extract::Path, validation::ValidatedJson},
+};
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RequestAssignee {
+    #[validate(range(min = 1, message = "must be a user id"))]
+    user_id: i32,
+}
+
+/// Ids of the users assigned to a ticket, in assignment order
+pub async fn assignee_ids(
+    db_conn: &DatabaseConnection,
+    ticket_id: i32,
+) -> Result<Vec<i32>, ApiError> {
+    Ok(assignees_by_ticket(db_conn, &[ticket_id])
+        .await?
+        .remove(&ticket_id)
+        .unwrap_or_default())
+}
+
+/// Assignee ids for a page of tickets, fetched in one query
+pub async fn assignees_by_ticket(
+    db_conn: &DatabaseConnection,
+    ticket_ids: &[i32],
+) -> Result<HashMap<i32, Vec<i32>>, ApiError> {
+    let assignees = TicketAssignees::find()
+        .filter(ticket_assignees::Column::TicketId.is_in(ticket_ids.iter().copied()))
+        .order_by_asc(ticket_assignees::Column::AssignedAt)
+        .order_by_asc(ticket_assignees::Column::UserId)
+        .all(db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to fetch assignees",
+            )
+        })?;
+
+    let mut by_ticket: HashMap<i32, Vec<i32>> = HashMap::new();
+    for assignee in assignees {
+        by_ticket
+            .entry(assignee.ticket_id)
+            .or_default()
+            .push(assignee.user_id);
+    }
+    Ok(by_ticket)
+}
+
+async fn find_ticket(
+    db_conn: &DatabaseConnection,
+    ticket_id: i32,
+) -> Result<tickets::Model, ApiError> {
+    Tickets::find_by_id(ticket_id)
+        .one(db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch ticket")
+        })?
+        .filter(|ticket| ticket.deleted_at.is_none())
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No ticket found"))
+}
+
+pub async fn assignee_add(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path(ticket_id): Path<i32>,
+    ValidatedJson(req_assignee): ValidatedJson<RequestAssignee>,
+) -> Result<StatusCode, ApiError> {
+    let ticket = find_ticket(&db_conn, ticket_id).await?;
+    let assignees = assignee_ids(&db_conn, ticket.id).await?;
+
+    ticket_policy::authorize(&user, &ticket, &assignees, TicketAction::Assign)?;
+
+    let assignee = Users::find_by_id(req_assignee.user_id)
+        .filter(users::Column::DeletedAt.is_null())
+        .one(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch user")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No user found"))?;
+
+    if assignee.role == UserRole::ReadOnly {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "Read-only users cannot be assigned tickets",
+        ));
+    }
+
+    let txn = db_conn.begin().await?;
+
+    // Assigning someone twice is a no-op
+    let inserted = TicketAssignees::insert(ticket_assignees::ActiveModel {
+        ticket_id: Set(ticket.id),
+        user_id: Set(assignee.id),
+        assigned_at: Set(Utc::now().fixed_offset()),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            ticket_assignees::Column::TicketId,
+            ticket_assignees::Column::UserId,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .do_nothing()
+    .exec(&txn)
+    .await
+    .map_err(|err| {
+        tracing::error!("Error: {:?}", err);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to assign ticket")
+    })?;
+
+    if let TryInsertResult::Inserted(_) = inserted {
+        touch_ticket(&txn, ticket.id).await?;
+        record_event(
+            &txn,
+            ticket.id,
+            user.id,
+            "assignees",
+            Value::Null,
+            json!(assignee.id),
+        )
+        .await?;
+    }
+    txn.commit().await?;
+
+    tracing::info!(
+        "Ticket {:?} assigned to User {:?} by User {:?}",
+        ticket.id,
+        assignee.id,
+        user.id
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn assignee_remove(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path((ticket_id, assignee_id)): Path<(i32, i32)>,
+) -> Result<StatusCode, ApiError> {
+    let ticket = find_ticket(&db_conn, ticket_id).await?;
+    let assignees = assignee_ids(&db_conn, ticket.id).await?;
+
+    // Assignees may always take themselves off a ticket
+    if assignee_id != user.id {
+        ticket_policy::authorize(&user, &ticket, &assignees, TicketAction::Assign)?;
+    }
+
+    if !assignees.contains(&assignee_id) {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "User is not assigned to this ticket",
+        ));
+    }
+
+    let txn = db_conn.begin().await?;
+
+    TicketAssignees::delete_by_id((ticket.id, assignee_id))
+        .exec(&txn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to unassign ticket",
+            )
+        })?;
+
+    touch_ticket(&txn, ticket.id).await?;
+    record_event(
+        &txn,
+        ticket.id,
+        user.id,
+        "assignees",
+        json!(assignee_id),
+        Value::Null,
+    )
+    .await?;
+    txn.commit().await?;
+
+    tracing::info!(
+        "User {:?} unassigned from ticket {:?} by User {:?}",
+        assignee_id,
+        ticket.id,
+        user.id
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,238 @@
+use axum::{Extension, Json, extract::State, http::StatusCode};
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use super::ticket_assignees::assignee_ids;
+use crate::{
+    database::{
+        ticket_comments::{self, Entity as TicketComments},
+        tickets::{self, Entity as Tickets},
+    },
+    middleware::guard::AuthUser,
+    policy::{
+        comment::{self as comment_policy, CommentAction},
+        ticket::{self as ticket_policy, TicketAction},
+    },
+    utils::{
+        api_error::ApiError,
+        extract::{Path, Query},
+        validation::{ValidatedJson, not_blank},
+    },
+};
+
+const DEFAULT_PAGE_LIMIT: u64 = 50;
+const MAX_PAGE_LIMIT: u64 = 100;
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RequestComment {
+    #[validate(
+        custom(function = "not_blank"),
+        length(max = 10000, message = "must be at most 10000 characters")
+    )]
+    body: String,
+}
+
+#[derive(Serialize)]
+pub struct ResponseComment {
+    id: i32,
+    ticket_id: i32,
+    user_id: i32,
+    body: String,
+    created_at: DateTime<FixedOffset>,
+    edited_at: Option<DateTime<FixedOffset>>,
+}
+
+impl From<ticket_comments::Model> for ResponseComment {
+    fn from(comment: ticket_comments::Model) -> Self {
+        Self {
+            id: comment.id,
+            ticket_id: comment.ticket_id,
+            user_id: comment.user_id,
+            body: comment.body,
+            created_at: comment.created_at,
+            edited_at: comment.edited_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ResponseCommentPage {
+    comments: Vec<ResponseComment>,
+    total: u64,
+    limit: u64,
+    offset: u64,
+}
+
+#[derive(Deserialize)]
+pub struct ListCommentsParams {
+    limit: Option<u64>,
+    #[serde(default)]
+    offset: u64,
+}
+
+/// Ticket the comments hang off, provided `user` may read it
+async fn find_ticket(
+    db_conn: &DatabaseConnection,
+    user: &AuthUser,
+    ticket_id: i32,
+) -> Result<tickets::Model, ApiError> {
+    let ticket = Tickets::find_by_id(ticket_id)
+        .one(db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch ticket")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No ticket found"))?;
+
+    let assignees = assignee_ids(db_conn, ticket.id).await?;
+    ticket_policy::authorize(user, &ticket, &assignees, TicketAction::Read)?;
+    Ok(ticket)
+}
+
+async fn find_comment(
+    db_conn: &DatabaseConnection,
+    ticket_id: i32,
+    comment_id: i32,
+) -> Result<ticket_comments::Model, ApiError> {
+    TicketComments::find_by_id(comment_id)
+        .filter(ticket_comments::Column::TicketId.eq(ticket_id))
+        .filter(ticket_comments::Column::DeletedAt.is_null())
+        .one(db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch comment")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No comment found"))
+}
+
+pub async fn comment_list(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path(ticket_id): Path<i32>,
+    Query(params): Query<ListCommentsParams>,
+) -> Result<Json<ResponseCommentPage>, ApiError> {
+    find_ticket(&db_conn, &user, ticket_id).await?;
+
+    let query = TicketComments::find()
+        .filter(ticket_comments::Column::TicketId.eq(ticket_id))
+        .filter(ticket_comments::Column::DeletedAt.is_null());
+
+    let total = query.clone().count(&db_conn).await.map_err(|err| {
+        tracing::error!("Error: {:?}", err);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error fetching comments")
+    })?;
+
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .min(MAX_PAGE_LIMIT);
+
+    let comments = query
+        .order_by_asc(ticket_comments::Column::CreatedAt)
+        .order_by_asc(ticket_comments::Column::Id)
+        .limit(limit)
+        .offset(params.offset)
+        .all(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error fetching comments")
+        })?
+        .into_iter()
+        .map(ResponseComment::from)
+        .collect();
+
+    Ok(Json(ResponseCommentPage {
+        comments,
+        total,
+        limit,
+        offset: params.offset,
+    }))
+}
+
+pub async fn comment_create(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path(ticket_id): Path<i32>,
+    ValidatedJson(req_comment): ValidatedJson<RequestComment>,
+) -> Result<(StatusCode, Json<ResponseComment>), ApiError> {
+    let ticket = find_ticket(&db_conn, &user, ticket_id).await?;
+    if ticket.deleted_at.is_some() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "No ticket found"));
+    }
+
+    let comment = ticket_comments::ActiveModel {
+        ticket_id: Set(ticket.id),
+        user_id: Set(user.id),
+        body: Set(req_comment.body),
+        created_at: Set(Utc::now().fixed_offset()),
+        ..Default::default()
+    }
+    .insert(&db_conn)
+    .await
+    .map_err(|err| {
+        tracing::error!("Error: {:?}", err);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to save comment")
+    })?;
+
+    tracing::info!("User {:?} commented on ticket {:?}", user.id, ticket.id);
+    Ok((StatusCode::CREATED, Json(ResponseComment::from(comment))))
+}
+
+pub async fn comment_update(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path((ticket_id, comment_id)): Path<(i32, i32)>,
+    ValidatedJson(req_comment): ValidatedJson<RequestComment>,
+) -> Result<Json<ResponseComment>, ApiError> {
+    find_ticket(&db_conn, &user, ticket_id).await?;
+    let comment = find_comment(&db_conn, ticket_id, comment_id).await?;
+
+    comment_policy::authorize(&user, &comment, CommentAction::Edit)?;
+
+    let mut comment = comment.into_active_model();
+    comment.body = Set(req_comment.body);
+    comment.edited_at = Set(Some(Utc::now().fixed_offset()));
+
+    let comment = comment.update(&db_conn).await.map_err(|err| {
+        tracing::error!("Error: {:?}", err);
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to update comment",
+        )
+    })?;
+
+    Ok(Json(ResponseComment::from(comment)))
+}
+
+pub async fn comment_delete(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path((ticket_id, comment_id)): Path<(i32, i32)>,
+) -> Result<StatusCode, ApiError> {
+    find_ticket(&db_conn, &user, ticket_id).await?;
+    let comment = find_comment(&db_conn, ticket_id, comment_id).await?;
+
+    comment_policy::authorize(&user, &comment, CommentAction::Delete)?;
+
+    let mut comment = comment.into_active_model();
+    comment.deleted_at = Set(Some(Utc::now().fixed_offset()));
+
+    comment.update(&db_conn).await.map_err(|err| {
+        tracing::error!("Error: {:?}", err);
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to delete comment",
+        )
+    })?;
+
+    tracing::info!("Comment {:?} deleted by User {:?}", comment_id, user.id);
+    Ok(StatusCode::NO_CONTENT)
+}
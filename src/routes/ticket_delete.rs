@@ -0,0 +1,197 @@
+use std::sync::Arc;
+
+use axum::{
+    Extension,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use chrono::Utc;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel, ModelTrait, QueryFilter,
+    Set, TransactionTrait,
+};
+use serde_json::{Value, json};
+
+use super::{
+    ticket_assignees::assignee_ids,
+    ticket_history::{record_changes, record_event},
+};
+use crate::{
+    config::Config,
+    database::tickets::{self, Entity as Tickets},
+    middleware::guard::AuthUser,
+    policy::ticket::{self as ticket_policy, TicketAction},
+    utils::{
+        api_error::ApiError,
+        extract::Path,
+        precondition::{check_if_match, ticket_changed, ticket_etag},
+    },
+};
+
+pub async fn ticket_delete(
+    State(db_conn): State<DatabaseConnection>,
+    State(config): State<Arc<Config>>,
+    Extension(user): Extension<AuthUser>,
+    Path(ticket_id): Path<i32>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    tracing::info!("Attempting to delete ticket {:?}", &ticket_id);
+    let ticket = Tickets::find_by_id(ticket_id)
+        .one(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch ticket")
+        })?
+        .filter(|ticket| ticket.deleted_at.is_none())
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No ticket found"))?;
+
+    let assignees = assignee_ids(&db_conn, ticket.id).await?;
+    ticket_policy::authorize(&user, &ticket, &assignees, TicketAction::Delete)?;
+    check_if_match(
+        &headers,
+        &ticket_etag(ticket.version),
+        config.tickets.require_if_match,
+    )?;
+
+    let mut deleted = ticket.clone().into_active_model();
+    deleted.deleted_at = Set(Some(Utc::now().fixed_offset()));
+    deleted.version = Set(ticket.version + 1);
+
+    let txn = db_conn.begin().await?;
+
+    let deleted = Tickets::update(deleted)
+        .filter(tickets::Column::Version.eq(ticket.version))
+        .exec(&txn)
+        .await
+        .map_err(|err| match err {
+            DbErr::RecordNotUpdated => ticket_changed(),
+            err => {
+                tracing::error!("Error: {:?}", err);
+                ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to delete ticket")
+            }
+        })?;
+
+    record_changes(&txn, user.id, &ticket, &deleted).await?;
+    txn.commit().await?;
+
+    tracing::info!("Ticket deleted");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn ticket_restore(
+    State(db_conn): State<DatabaseConnection>,
+    State(config): State<Arc<Config>>,
+    Extension(user): Extension<AuthUser>,
+    Path(ticket_id): Path<i32>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    tracing::info!("Attempting to restore ticket {:?}", &ticket_id);
+    let ticket = Tickets::find_by_id(ticket_id)
+        .one(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch ticket")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No ticket found"))?;
+
+    let assignees = assignee_ids(&db_conn, ticket.id).await?;
+    ticket_policy::authorize(&user, &ticket, &assignees, TicketAction::Delete)?;
+
+    check_if_match(
+        &headers,
+        &ticket_etag(ticket.version),
+        config.tickets.require_if_match,
+    )?;
+
+    if ticket.deleted_at.is_none() {
+        return Err(ApiError::new(StatusCode::CONFLICT, "Ticket is not deleted"));
+    }
+
+    let mut restored = ticket.clone().into_active_model();
+    restored.deleted_at = Set(None);
+    restored.version = Set(ticket.version + 1);
+
+    let txn = db_conn.begin().await?;
+
+    let restored = Tickets::update(restored)
+        .filter(tickets::Column::Version.eq(ticket.version))
+        .exec(&txn)
+        .await
+        .map_err(|err| match err {
+            DbErr::RecordNotUpdated => ticket_changed(),
+            err => {
+                tracing::error!("Error: {:?}", err);
+                ApiError::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to restore ticket",
+                )
+            }
+        })?;
+
+    record_changes(&txn, user.id, &ticket, &restored).await?;
+    txn.commit().await?;
+
+    tracing::info!("Ticket restored");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Permanently removes a ticket
+///
+/// Its comments, assignees and labels go with it. Its history stays behind,
+/// detached from the ticket, and ends in a `purged` event naming the ticket
+/// and who purged it.
+pub async fn ticket_purge(
+    State(db_conn): State<DatabaseConnection>,
+    State(config): State<Arc<Config>>,
+    Extension(user): Extension<AuthUser>,
+    Path(ticket_id): Path<i32>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    tracing::info!("Attempting to purge ticket {:?}", &ticket_id);
+    let ticket = Tickets::find_by_id(ticket_id)
+        .one(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch ticket")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No ticket found"))?;
+
+    let assignees = assignee_ids(&db_conn, ticket.id).await?;
+    ticket_policy::authorize(&user, &ticket, &assignees, TicketAction::Delete)?;
+    check_if_match(
+        &headers,
+        &ticket_etag(ticket.version),
+        config.tickets.require_if_match,
+    )?;
+
+    let txn = db_conn.begin().await?;
+
+    // The event outlives the ticket, so it carries what identified it
+    record_event(
+        &txn,
+        ticket.id,
+        user.id,
+        "purged",
+        json!({
+            "id": ticket.id,
+            "project_id": ticket.project_id,
+            "number": ticket.number,
+            "title": ticket.title,
+        }),
+        Value::Null,
+    )
+    .await?;
+
+    ticket.delete(&txn).await.map_err(|err| {
+        tracing::error!("Error: {:?}", err);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to purge ticket")
+    })?;
+
+    txn.commit().await?;
+
+    tracing::info!("Ticket purged by User {:?}", user.id);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,72 +1,419 @@
-use axum::extract::Path;
-use axum::http::StatusCode;
-use axum::{Json, extract::State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json, extract::State};
+use axum_extra::TypedHeader;
 use chrono::{DateTime, FixedOffset};
-use sea_orm::{DatabaseConnection, EntityTrait};
-use serde::Serialize;
+use sea_orm::sea_query::Query as SelectQuery;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, Order, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect,
+};
+use serde::{Deserialize, Serialize};
 
-use crate::database::tickets::Entity as Tickets;
+use super::{
+    projects::{find_project, project_keys},
+    ticket_assignees::{assignee_ids, assignees_by_ticket},
+    ticket_labels::{self, LabelMatch, label_names, labels_by_ticket},
+    ticket_query::{self, QueryError, title_contains},
+};
+use crate::database::{
+    projects,
+    sea_orm_active_enums::{TicketPriority, TicketStatus},
+    tickets::{self, Entity as Tickets},
+};
+use crate::middleware::guard::AuthUser;
+use crate::policy::ticket::{self as ticket_policy, TicketAction};
 use crate::utils::api_error::ApiError;
+use crate::utils::extract::{Path, Query};
+use crate::utils::precondition::{is_not_modified, ticket_etag};
+
+const DEFAULT_PAGE_LIMIT: u64 = 50;
+const MAX_PAGE_LIMIT: u64 = 100;
 
 #[derive(Serialize)]
 pub struct ResponseTicket {
     id: i32,
     title: String,
     description: Option<String>,
-    priority: Option<String>,
+    priority: Option<TicketPriority>,
+    status: TicketStatus,
+    completed_at: Option<DateTime<FixedOffset>>,
     deleted_at: Option<DateTime<FixedOffset>>,
+    /// Reporter of the ticket
     user_id: Option<i32>,
+    assignees: Vec<i32>,
+    /// Label names, alphabetically
+    labels: Vec<String>,
+    project_id: Option<i32>,
+    /// Human readable id within the project, like `OPS-42`
+    key: Option<String>,
+    /// Bumped on every change, also sent as the `ETag`
+    version: i32,
+    created_at: DateTime<FixedOffset>,
 }
 
-// #[derive(Deserialize)]
-// pub struct FilterTicketsParams {
-//     priority: Option<String>,
-// }
+impl ResponseTicket {
+    pub fn new(
+        ticket: tickets::Model,
+        assignees: Vec<i32>,
+        labels: Vec<String>,
+        project_key: Option<String>,
+    ) -> Self {
+        let key = project_key
+            .zip(ticket.number)
+            .map(|(project_key, number)| format!("{project_key}-{number}"));
 
-// FIX: impl query filtering and remove deleted tics
-pub async fn ticket_get_all(
-    State(db_conn): State<DatabaseConnection>,
-) -> Result<Json<Vec<ResponseTicket>>, ApiError> {
-    let tickets = Tickets::find()
-        .all(&db_conn)
-        .await
-        .map_err(|err| {
-            tracing::error!("Error: {:?}", err);
-            ApiError::new(StatusCode::UNAUTHORIZED, "Error fetching tickets")
-        })?
-        .into_iter()
-        .map(|ticket| ResponseTicket {
+        Self {
             id: ticket.id,
             title: ticket.title,
             description: ticket.description,
             priority: ticket.priority,
+            status: ticket.status,
+            completed_at: ticket.completed_at,
             deleted_at: ticket.deleted_at,
             user_id: ticket.user_id,
-        })
-        .collect();
+            assignees,
+            labels,
+            project_id: ticket.project_id,
+            key,
+            version: ticket.version,
+            created_at: ticket.created_at,
+        }
+    }
+
+    /// Builds the response for a single ticket, loading its labels and project
+    pub async fn load(
+        db_conn: &DatabaseConnection,
+        ticket: tickets::Model,
+        assignees: Vec<i32>,
+    ) -> Result<Self, ApiError> {
+        let labels = label_names(db_conn, ticket.id).await?;
+        let project_key = match ticket.project_id {
+            Some(project_id) => project_keys(db_conn, &[project_id])
+                .await?
+                .remove(&project_id),
+            None => None,
+        };
+
+        Ok(Self::new(ticket, assignees, labels, project_key))
+    }
+
+    /// Builds the responses for a page of tickets, batching the lookups of
+    /// their assignees, labels and projects
+    pub async fn load_many(
+        db_conn: &DatabaseConnection,
+        tickets: Vec<tickets::Model>,
+    ) -> Result<Vec<Self>, ApiError> {
+        let ticket_ids: Vec<i32> = tickets.iter().map(|ticket| ticket.id).collect();
+        let mut assignees = assignees_by_ticket(db_conn, &ticket_ids).await?;
+        let mut labels = labels_by_ticket(db_conn, &ticket_ids).await?;
+        let mut project_ids: Vec<i32> = tickets
+            .iter()
+            .filter_map(|ticket| ticket.project_id)
+            .collect();
+        project_ids.sort_unstable();
+        project_ids.dedup();
+        let keys = project_keys(db_conn, &project_ids).await?;
+
+        let tickets = tickets
+            .into_iter()
+            .map(|ticket| {
+                let ticket_assignees = assignees.remove(&ticket.id).unwrap_or_default();
+                let ticket_labels = labels.remove(&ticket.id).unwrap_or_default();
+                let project_key = ticket
+                    .project_id
+                    .and_then(|project_id| keys.get(&project_id).cloned());
+                ResponseTicket::new(ticket, ticket_assignees, ticket_labels, project_key)
+            })
+            .collect();
+
+        Ok(tickets)
+    }
+}
+
+#[derive(Serialize)]
+pub struct ResponseTicketPage {
+    tickets: Vec<ResponseTicket>,
+    total: u64,
+    limit: u64,
+    offset: u64,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    #[default]
+    Id,
+    Title,
+    Priority,
+    Status,
+    CompletedAt,
+    CreatedAt,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Filters and sort order of a ticket listing, saved views store them as JSON
+#[derive(Deserialize, Serialize)]
+pub struct FilterTicketsParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<TicketPriority>,
+    /// Reporter of the ticket
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_id: Option<i32>,
+    /// Only tickets assigned to this user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    assignee_id: Option<i32>,
+    /// Key of the project the tickets belong to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<TicketStatus>,
+    /// Case-insensitive substring match on the ticket title
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    /// Query language expression, see `ticket_query::compile`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    q: Option<String>,
+    /// Comma-separated label names
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    /// Whether tickets need `any` or `all` of the labels in `label`
+    #[serde(default)]
+    label_match: LabelMatch,
+    #[serde(default)]
+    sort: SortField,
+    #[serde(default)]
+    order: SortOrder,
+    #[serde(skip_serializing)]
+    limit: Option<u64>,
+    #[serde(default, skip_serializing)]
+    offset: u64,
+    /// Also list soft-deleted tickets owned by the caller
+    #[serde(default)]
+    include_deleted: bool,
+}
+
+impl FilterTicketsParams {
+    /// The same filters, showing another page
+    pub fn paged(self, limit: Option<u64>, offset: u64) -> Self {
+        Self {
+            limit,
+            offset,
+            ..self
+        }
+    }
+
+    /// Catches a broken `q` before the filters are stored
+    pub fn check_query(&self, user: &AuthUser) -> Result<(), QueryError> {
+        match &self.q {
+            Some(q) => ticket_query::compile(q, user).map(|_condition| ()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct GetTicketParams {
+    #[serde(default)]
+    include_deleted: bool,
+}
+
+pub async fn ticket_get_all(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<FilterTicketsParams>,
+) -> Result<Json<ResponseTicketPage>, ApiError> {
+    find_tickets(&db_conn, &user, params).await.map(Json)
+}
+
+/// Tickets assigned to the caller, with the same filters as `ticket_get_all`
+pub async fn ticket_get_assigned(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Query(mut params): Query<FilterTicketsParams>,
+) -> Result<Json<ResponseTicketPage>, ApiError> {
+    params.assignee_id = Some(user.id);
+    find_tickets(&db_conn, &user, params).await.map(Json)
+}
+
+/// Tickets of a single project, with the same filters as `ticket_get_all`
+pub async fn ticket_get_project(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path(key): Path<String>,
+    Query(mut params): Query<FilterTicketsParams>,
+) -> Result<Json<ResponseTicketPage>, ApiError> {
+    let project = find_project(&db_conn, &key).await?;
+    params.project = Some(project.key);
+    find_tickets(&db_conn, &user, params).await.map(Json)
+}
 
-    Ok(Json(tickets))
+pub async fn find_tickets(
+    db_conn: &DatabaseConnection,
+    user: &AuthUser,
+    params: FilterTicketsParams,
+) -> Result<ResponseTicketPage, ApiError> {
+    let mut query = Tickets::find().filter(ticket_policy::visible_to(user, params.include_deleted));
+
+    if let Some(priority) = params.priority {
+        query = query.filter(tickets::Column::Priority.eq(priority));
+    }
+
+    if let Some(user_id) = params.user_id {
+        query = query.filter(tickets::Column::UserId.eq(user_id));
+    }
+
+    if let Some(assignee_id) = params.assignee_id {
+        query = query.filter(ticket_policy::assigned_to(assignee_id));
+    }
+
+    if let Some(project) = params.project {
+        query = query.filter(
+            tickets::Column::ProjectId.in_subquery(
+                SelectQuery::select()
+                    .column(projects::Column::Id)
+                    .from(projects::Entity)
+                    .and_where(projects::Column::Key.eq(project.to_uppercase()))
+                    .to_owned(),
+            ),
+        );
+    }
+
+    if let Some(status) = params.status {
+        query = query.filter(tickets::Column::Status.eq(status));
+    }
+
+    if let Some(title) = params.title {
+        query = query.filter(title_contains(&title));
+    }
+
+    if let Some(q) = params.q {
+        query = query.filter(ticket_query::compile(&q, user)?);
+    }
+
+    if let Some(label) = params.label {
+        let mut names: Vec<String> = label
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_owned)
+            .collect();
+        names.sort();
+        names.dedup();
+
+        if !names.is_empty() {
+            query = query.filter(ticket_labels::labelled(&names, params.label_match));
+        }
+    }
+
+    let total = query.clone().count(db_conn).await.map_err(|err| {
+        tracing::error!("Error: {:?}", err);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error fetching tickets")
+    })?;
+
+    let sort_column = match params.sort {
+        SortField::Id => tickets::Column::Id,
+        SortField::Title => tickets::Column::Title,
+        SortField::Priority => tickets::Column::Priority,
+        SortField::Status => tickets::Column::Status,
+        SortField::CompletedAt => tickets::Column::CompletedAt,
+        SortField::CreatedAt => tickets::Column::CreatedAt,
+    };
+    let order = match params.order {
+        SortOrder::Asc => Order::Asc,
+        SortOrder::Desc => Order::Desc,
+    };
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .min(MAX_PAGE_LIMIT);
+
+    let tickets = query
+        .order_by(sort_column, order)
+        // Tie-break on id so pages stay stable when sorting on non-unique columns
+        .order_by_asc(tickets::Column::Id)
+        .limit(limit)
+        .offset(params.offset)
+        .all(db_conn)
+        .await?;
+
+    Ok(ResponseTicketPage {
+        tickets: ResponseTicket::load_many(db_conn, tickets).await?,
+        total,
+        limit,
+        offset: params.offset,
+    })
 }
 
 pub async fn ticket_get_single(
     State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
     Path(ticket_id): Path<i32>,
-) -> Result<Json<ResponseTicket>, ApiError> {
+    Query(params): Query<GetTicketParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
     let ticket = Tickets::find_by_id(ticket_id)
         //
         .one(&db_conn)
         .await
-        .unwrap();
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch ticket")
+        })?;
+
+    respond_single(&db_conn, &user, ticket, params, &headers).await
+}
+
+/// A ticket by its project key and number, e.g. `/projects/OPS/tickets/42`
+pub async fn ticket_get_by_key(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path((key, number)): Path<(String, i32)>,
+    Query(params): Query<GetTicketParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let project = find_project(&db_conn, &key).await?;
+    let ticket = Tickets::find()
+        .filter(tickets::Column::ProjectId.eq(project.id))
+        .filter(tickets::Column::Number.eq(number))
+        .one(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch ticket")
+        })?;
+
+    respond_single(&db_conn, &user, ticket, params, &headers).await
+}
+
+/// Answers with the ticket and its `ETag`, or 304 when the client's copy is current
+async fn respond_single(
+    db_conn: &DatabaseConnection,
+    user: &AuthUser,
+    ticket: Option<tickets::Model>,
+    params: GetTicketParams,
+    headers: &HeaderMap,
+) -> Result<Response, ApiError> {
+    let ticket = ticket.filter(|ticket| params.include_deleted || ticket.deleted_at.is_none());
 
     if let Some(ticket) = ticket {
-        Ok(Json(ResponseTicket {
-            id: ticket.id,
-            title: ticket.title,
-            description: ticket.description,
-            priority: ticket.priority,
-            deleted_at: ticket.deleted_at,
-            user_id: ticket.user_id,
-        }))
+        let assignees = assignee_ids(db_conn, ticket.id).await?;
+        ticket_policy::authorize(user, &ticket, &assignees, TicketAction::Read)?;
+
+        let etag = ticket_etag(ticket.version);
+        if is_not_modified(headers, &etag)? {
+            return Ok((StatusCode::NOT_MODIFIED, TypedHeader(etag)).into_response());
+        }
+
+        let ticket = ResponseTicket::load(db_conn, ticket, assignees).await?;
+        Ok((TypedHeader(etag), Json(ticket)).into_response())
     } else {
         Err(ApiError::new(
             StatusCode::NOT_FOUND,
@@ -0,0 +1,230 @@
+use axum::{Extension, Json, extract::State, http::StatusCode};
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set, prelude::Expr,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use super::ticket_assignees::assignee_ids;
+use crate::{
+    database::{
+        ticket_events::{self, Entity as TicketEvents},
+        tickets::{self, Entity as Tickets},
+    },
+    middleware::guard::AuthUser,
+    policy::ticket::{self as ticket_policy, TicketAction},
+    utils::{
+        api_error::ApiError,
+        extract::{Path, Query},
+    },
+};
+
+const DEFAULT_PAGE_LIMIT: u64 = 50;
+const MAX_PAGE_LIMIT: u64 = 100;
+
+#[derive(Serialize)]
+pub struct ResponseTicketEvent {
+    id: i32,
+    /// User who made the change
+    actor_id: Option<i32>,
+    field: String,
+    old_value: Option<Value>,
+    new_value: Option<Value>,
+    created_at: DateTime<FixedOffset>,
+}
+
+impl From<ticket_events::Model> for ResponseTicketEvent {
+    fn from(event: ticket_events::Model) -> Self {
+        Self {
+            id: event.id,
+            actor_id: event.actor_id,
+            field: event.field,
+            old_value: event.old_value,
+            new_value: event.new_value,
+            created_at: event.created_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ResponseTicketHistory {
+    events: Vec<ResponseTicketEvent>,
+    total: u64,
+    limit: u64,
+    offset: u64,
+}
+
+#[derive(Deserialize)]
+pub struct HistoryParams {
+    limit: Option<u64>,
+    #[serde(default)]
+    offset: u64,
+}
+
+fn event(
+    ticket_id: i32,
+    actor_id: i32,
+    field: &str,
+    old_value: Value,
+    new_value: Value,
+) -> ticket_events::ActiveModel {
+    ticket_events::ActiveModel {
+        ticket_id: Set(Some(ticket_id)),
+        actor_id: Set(Some(actor_id)),
+        field: Set(field.to_owned()),
+        old_value: Set(Some(old_value).filter(|value| !value.is_null())),
+        new_value: Set(Some(new_value).filter(|value| !value.is_null())),
+        created_at: Set(Utc::now().fixed_offset()),
+        ..Default::default()
+    }
+}
+
+/// Bumps the version of a ticket whose assignees or labels changed, they are
+/// part of its representation and so of its `ETag`
+pub async fn touch_ticket(conn: &impl ConnectionTrait, ticket_id: i32) -> Result<(), ApiError> {
+    Tickets::update_many()
+        .col_expr(
+            tickets::Column::Version,
+            Expr::col(tickets::Column::Version).add(1),
+        )
+        .filter(tickets::Column::Id.eq(ticket_id))
+        .exec(conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to update ticket")
+        })?;
+
+    Ok(())
+}
+
+/// Records one change to a ticket, meant to run in the transaction making it
+pub async fn record_event(
+    conn: &impl ConnectionTrait,
+    ticket_id: i32,
+    actor_id: i32,
+    field: &str,
+    old_value: Value,
+    new_value: Value,
+) -> Result<(), ApiError> {
+    TicketEvents::insert(event(ticket_id, actor_id, field, old_value, new_value))
+        .exec(conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to record ticket history",
+            )
+        })?;
+
+    Ok(())
+}
+
+/// Records every field that differs between two versions of a ticket
+pub async fn record_changes(
+    conn: &impl ConnectionTrait,
+    actor_id: i32,
+    before: &tickets::Model,
+    after: &tickets::Model,
+) -> Result<(), ApiError> {
+    let fields = [
+        ("title", json!(before.title), json!(after.title)),
+        (
+            "description",
+            json!(before.description),
+            json!(after.description),
+        ),
+        ("priority", json!(before.priority), json!(after.priority)),
+        ("status", json!(before.status), json!(after.status)),
+        (
+            "completed_at",
+            json!(before.completed_at),
+            json!(after.completed_at),
+        ),
+        (
+            "deleted_at",
+            json!(before.deleted_at),
+            json!(after.deleted_at),
+        ),
+    ];
+
+    let events: Vec<_> = fields
+        .into_iter()
+        .filter(|(_, old_value, new_value)| old_value != new_value)
+        .map(|(field, old_value, new_value)| event(after.id, actor_id, field, old_value, new_value))
+        .collect();
+
+    if events.is_empty() {
+        return Ok(());
+    }
+
+    TicketEvents::insert_many(events)
+        .exec(conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to record ticket history",
+            )
+        })?;
+
+    Ok(())
+}
+
+/// Changes made to a ticket, oldest first
+pub async fn ticket_history(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path(ticket_id): Path<i32>,
+    Query(params): Query<HistoryParams>,
+) -> Result<Json<ResponseTicketHistory>, ApiError> {
+    let ticket = Tickets::find_by_id(ticket_id)
+        .one(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch ticket")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No ticket found"))?;
+
+    let assignees = assignee_ids(&db_conn, ticket.id).await?;
+    ticket_policy::authorize(&user, &ticket, &assignees, TicketAction::Read)?;
+
+    let query = TicketEvents::find().filter(ticket_events::Column::TicketId.eq(ticket.id));
+
+    let total = query.clone().count(&db_conn).await.map_err(|err| {
+        tracing::error!("Error: {:?}", err);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error fetching history")
+    })?;
+
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .min(MAX_PAGE_LIMIT);
+
+    let events = query
+        .order_by_asc(ticket_events::Column::CreatedAt)
+        .order_by_asc(ticket_events::Column::Id)
+        .limit(limit)
+        .offset(params.offset)
+        .all(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error fetching history")
+        })?
+        .into_iter()
+        .map(ResponseTicketEvent::from)
+        .collect();
+
+    Ok(Json(ResponseTicketHistory {
+        events,
+        total,
+        limit,
+        offset: params.offset,
+    }))
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use axum::{Extension, extract::State, http::StatusCode};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+    TransactionTrait, TryInsertResult,
+    sea_query::{Expr, OnConflict, Query},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use validator::Validate;
+
+use super::{
+    ticket_assignees::assignee_ids,
+    ticket_history::{record_event, touch_ticket},
+};
+use crate::{
+    database::{
+        labels::{self, Entity as Labels},
+        ticket_labels::{self, Entity as TicketLabels},
+        tickets::{self, Entity as Tickets},
+    },
+    middleware::guard::AuthUser,
+    policy::ticket::{self as ticket_policy, TicketAction},
+    utils::{api_error::ApiError, extract::Path, validation::ValidatedJson},
+};
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RequestTicketLabel {
+    #[validate(range(min = 1, message = "must be a label id"))]
+    label_id: i32,
+}
+
+/// How a list of labels in a ticket filter is combined
+#[derive(Deserialize, Serialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelMatch {
+    /// Tickets carrying at least one of the labels
+    #[default]
+    Any,
+    /// Tickets carrying every one of the labels
+    All,
+}
+
+/// Label names of a single ticket, alphabetically
+pub async fn label_names(
+    db_conn: &DatabaseConnection,
+    ticket_id: i32,
+) -> Result<Vec<String>, ApiError> {
+    Ok(labels_by_ticket(db_conn, &[ticket_id])
+        .await?
+        .remove(&ticket_id)
+        .unwrap_or_default())
+}
+
+/// Label names for a page of tickets, fetched in one query
+pub async fn labels_by_ticket(
+    db_conn: &DatabaseConnection,
+    ticket_ids: &[i32],
+) -> Result<HashMap<i32, Vec<String>>, ApiError> {
+    let ticket_labels = TicketLabels::find()
+        .find_also_related(Labels)
+        .filter(ticket_labels::Column::TicketId.is_in(ticket_ids.iter().copied()))
+        .order_by_asc(labels::Column::Name)
+        .all(db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch labels")
+        })?;
+
+    let mut by_ticket: HashMap<i32, Vec<String>> = HashMap::new();
+    for (ticket_label, label) in ticket_labels {
+        if let Some(label) = label {
+            by_ticket
+                .entry(ticket_label.ticket_id)
+                .or_default()
+                .push(label.name);
+        }
+    }
+    Ok(by_ticket)
+}
+
+/// Query condition matching the tickets labelled with `names`
+pub fn labelled(names: &[String], label_match: LabelMatch) -> Condition {
+    let mut labelled_tickets = Query::select()
+        .column((ticket_labels::Entity, ticket_labels::Column::TicketId))
+        .from(ticket_labels::Entity)
+        .inner_join(
+            labels::Entity,
+            Expr::col((labels::Entity, labels::Column::Id))
+                .equals((ticket_labels::Entity, ticket_labels::Column::LabelId)),
+        )
+        .and_where(labels::Column::Name.is_in(names.iter().cloned()))
+        .to_owned();
+
+    if let LabelMatch::All = label_match {
+        // Label names are unique, so a ticket matching every name has one row per name
+        labelled_tickets
+            .group_by_col((ticket_labels::Entity, ticket_labels::Column::TicketId))
+            .and_having(
+                Expr::col((ticket_labels::Entity, ticket_labels::Column::LabelId))
+                    .count()
+                    .eq(names.len() as i64),
+            );
+    }
+
+    Condition::all().add(tickets::Column::Id.in_subquery(labelled_tickets))
+}
+
+async fn find_ticket(
+    db_conn: &DatabaseConnection,
+    user: &AuthUser,
+    ticket_id: i32,
+) -> Result<tickets::Model, ApiError> {
+    let ticket = Tickets::find_by_id(ticket_id)
+        .one(db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch ticket")
+        })?
+        .filter(|ticket| ticket.deleted_at.is_none())
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No ticket found"))?;
+
+    let assignees = assignee_ids(db_conn, ticket.id).await?;
+    ticket_policy::authorize(user, &ticket, &assignees, TicketAction::Edit)?;
+    Ok(ticket)
+}
+
+async fn find_label(
+    db_conn: &DatabaseConnection,
+    label_id: i32,
+) -> Result<labels::Model, ApiError> {
+    Labels::find_by_id(label_id)
+        .one(db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch label")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No label found"))
+}
+
+pub async fn ticket_label_add(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path(ticket_id): Path<i32>,
+    ValidatedJson(req_label): ValidatedJson<RequestTicketLabel>,
+) -> Result<StatusCode, ApiError> {
+    let ticket = find_ticket(&db_conn, &user, ticket_id).await?;
+
+    let label = find_label(&db_conn, req_label.label_id).await?;
+
+    let txn = db_conn.begin().await?;
+
+    // Attaching a label twice is a no-op
+    let inserted = TicketLabels::insert(ticket_labels::ActiveModel {
+        ticket_id: Set(ticket.id),
+        label_id: Set(label.id),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            ticket_labels::Column::TicketId,
+            ticket_labels::Column::LabelId,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .do_nothing()
+    .exec(&txn)
+    .await
+    .map_err(|err| {
+        tracing::error!("Error: {:?}", err);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to label ticket")
+    })?;
+
+    if let TryInsertResult::Inserted(_) = inserted {
+        touch_ticket(&txn, ticket.id).await?;
+        record_event(
+            &txn,
+            ticket.id,
+            user.id,
+            "labels",
+            Value::Null,
+            json!(label.name),
+        )
+        .await?;
+    }
+    txn.commit().await?;
+
+    tracing::info!(
+        "Label {:?} added to ticket {:?} by User {:?}",
+        label.id,
+        ticket.id,
+        user.id
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn ticket_label_remove(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path((ticket_id, label_id)): Path<(i32, i32)>,
+) -> Result<StatusCode, ApiError> {
+    let ticket = find_ticket(&db_conn, &user, ticket_id).await?;
+    let label = find_label(&db_conn, label_id).await?;
+
+    let txn = db_conn.begin().await?;
+
+    let removed = TicketLabels::delete_by_id((ticket.id, label.id))
+        .exec(&txn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to unlabel ticket",
+            )
+        })?;
+
+    if removed.rows_affected == 0 {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "Ticket does not carry this label",
+        ));
+    }
+
+    touch_ticket(&txn, ticket.id).await?;
+    record_event(
+        &txn,
+        ticket.id,
+        user.id,
+        "labels",
+        json!(label.name),
+        Value::Null,
+    )
+    .await?;
+    txn.commit().await?;
+
+    tracing::info!(
+        "Label {:?} removed from ticket {:?} by User {:?}",
+        label_id,
+        ticket.id,
+        user.id
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
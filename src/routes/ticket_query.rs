@@ -0,0 +1,673 @@
+use std::slice;
+
+use axum::http::StatusCode;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate};
+use sea_orm::{
+    ActiveEnum, ColumnTrait, Condition,
+    sea_query::{Expr, IntoCondition, Query, SimpleExpr, extension::postgres::PgExpr},
+};
+
+use super::ticket_labels::{self, LabelMatch};
+use crate::{
+    database::{
+        projects,
+        sea_orm_active_enums::{TicketPriority, TicketStatus},
+        ticket_assignees, tickets, users,
+    },
+    middleware::guard::AuthUser,
+    utils::api_error::{ApiError, FieldError},
+};
+
+const FIELDS: &str =
+    "priority, status, assignee, reporter, label, project, title, created, completed";
+
+/// Syntax or meaning error in a ticket query
+#[derive(Debug)]
+pub struct QueryError {
+    /// Position of the offending character, counting from 1
+    pub column: usize,
+    pub reason: String,
+}
+
+impl From<QueryError> for ApiError {
+    fn from(err: QueryError) -> Self {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("Invalid query at column {}: {}", err.column, err.reason),
+        )
+        .with_code("invalid_query")
+        .with_errors(vec![FieldError {
+            field: "q".to_owned(),
+            reason: format!("column {}: {}", err.column, err.reason),
+        }])
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Comparison {
+    Before,
+    AtOrBefore,
+    After,
+    AtOrAfter,
+}
+
+#[derive(Debug)]
+struct Value {
+    text: String,
+    /// Index of the first character, counting from 0
+    start: usize,
+}
+
+#[derive(Debug)]
+enum TermKind {
+    /// Bare word, matched against the title
+    Text(Value),
+    Field {
+        name: Value,
+        comparison: Option<(Comparison, usize)>,
+        values: Vec<Value>,
+    },
+}
+
+#[derive(Debug)]
+struct Term {
+    negated: bool,
+    kind: TermKind,
+}
+
+fn error(start: usize, reason: impl Into<String>) -> QueryError {
+    QueryError {
+        column: start + 1,
+        reason: reason.into(),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(input: &str) -> Self {
+        Self {
+            chars: input.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn at_term_end(&self) -> bool {
+        self.peek().is_none_or(char::is_whitespace)
+    }
+
+    fn parse(mut self) -> Result<Vec<Term>, QueryError> {
+        let mut terms = Vec::new();
+
+        loop {
+            while self.peek().is_some_and(char::is_whitespace) {
+                self.pos += 1;
+            }
+            if self.peek().is_none() {
+                return Ok(terms);
+            }
+            terms.push(self.term()?);
+        }
+    }
+
+    /// `[-]field:[comparison]value[,value...]` or `[-]word`
+    fn term(&mut self) -> Result<Term, QueryError> {
+        let negated = self.peek() == Some('-')
+            && self
+                .chars
+                .get(self.pos + 1)
+                .is_some_and(|c| !c.is_whitespace());
+        if negated {
+            self.pos += 1;
+        }
+
+        let name_start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        {
+            self.pos += 1;
+        }
+
+        if self.pos == name_start || self.peek() != Some(':') {
+            self.pos = name_start;
+            let word = self.value(true)?;
+            return Ok(Term {
+                negated,
+                kind: TermKind::Text(word),
+            });
+        }
+
+        let name = Value {
+            text: self.chars[name_start..self.pos]
+                .iter()
+                .collect::<String>()
+                .to_lowercase(),
+            start: name_start,
+        };
+        self.pos += 1;
+
+        let comparison = self.comparison();
+        let mut values = vec![self.value(false)?];
+        while self.peek() == Some(',') {
+            self.pos += 1;
+            values.push(self.value(false)?);
+        }
+
+        if !self.at_term_end() {
+            return Err(error(
+                self.pos,
+                format!(
+                    "unexpected {:?} after value",
+                    self.peek().unwrap_or_default()
+                ),
+            ));
+        }
+
+        Ok(Term {
+            negated,
+            kind: TermKind::Field {
+                name,
+                comparison,
+                values,
+            },
+        })
+    }
+
+    fn comparison(&mut self) -> Option<(Comparison, usize)> {
+        let start = self.pos;
+        let comparison = match (self.peek()?, self.chars.get(self.pos + 1)) {
+            ('<', Some('=')) => Comparison::AtOrBefore,
+            ('>', Some('=')) => Comparison::AtOrAfter,
+            ('<', _) => Comparison::Before,
+            ('>', _) => Comparison::After,
+            _ => return None,
+        };
+        self.pos += match comparison {
+            Comparison::AtOrBefore | Comparison::AtOrAfter => 2,
+            Comparison::Before | Comparison::After => 1,
+        };
+
+        Some((comparison, start))
+    }
+
+    /// A `"quoted value"` or a run of characters up to whitespace, and up to
+    /// a comma unless it is a bare word
+    fn value(&mut self, bare_word: bool) -> Result<Value, QueryError> {
+        let start = self.pos;
+
+        let text: String = if self.peek() == Some('"') {
+            self.pos += 1;
+            let end = self.chars[self.pos..]
+                .iter()
+                .position(|&c| c == '"')
+                .ok_or_else(|| error(start, "unterminated quote"))?;
+            let text = self.chars[self.pos..self.pos + end].iter().collect();
+            self.pos += end + 1;
+            text
+        } else {
+            while self
+                .peek()
+                .is_some_and(|c| !c.is_whitespace() && c != '"' && (bare_word || c != ','))
+            {
+                self.pos += 1;
+            }
+            self.chars[start..self.pos].iter().collect()
+        };
+
+        if text.trim().is_empty() {
+            return Err(error(start, "expected a value"));
+        }
+        if bare_word && !self.at_term_end() {
+            return Err(error(
+                self.pos,
+                format!("unexpected {:?}", self.peek().unwrap_or_default()),
+            ));
+        }
+
+        Ok(Value { text, start })
+    }
+}
+
+/// Case-insensitive substring match on the title
+pub fn title_contains(text: &str) -> SimpleExpr {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    Expr::col((tickets::Entity, tickets::Column::Title)).ilike(format!("%{escaped}%"))
+}
+
+/// Wraps a condition on a nullable column, so negating it keeps the rows
+/// where the column is null
+fn present(column: tickets::Column, condition: impl IntoCondition) -> Condition {
+    Condition::all()
+        .add(column.is_not_null())
+        .add(condition.into_condition())
+}
+
+fn user_matches(column: impl ColumnTrait, value: &Value, user: &AuthUser) -> SimpleExpr {
+    if value.text.eq_ignore_ascii_case("me") {
+        column.eq(user.id)
+    } else if let Ok(user_id) = value.text.parse::<i32>() {
+        column.eq(user_id)
+    } else {
+        column.in_subquery(
+            Query::select()
+                .column(users::Column::Id)
+                .from(users::Entity)
+                .and_where(users::Column::Username.eq(&value.text))
+                .to_owned(),
+        )
+    }
+}
+
+fn priority(value: &Value) -> Result<Condition, QueryError> {
+    let text = value.text.to_lowercase();
+    if text == "none" {
+        return Ok(Condition::all().add(tickets::Column::Priority.is_null()));
+    }
+
+    // The letter grades tickets were once filed with
+    let level = match text.as_str() {
+        "a" => "urgent",
+        "b" => "high",
+        "c" => "medium",
+        "d" => "low",
+        level => level,
+    };
+    let priority = TicketPriority::try_from_value(&level.to_owned()).map_err(|_err| {
+        error(
+            value.start,
+            format!(
+                "unknown priority {:?}, expected low, medium, high, urgent or none",
+                value.text
+            ),
+        )
+    })?;
+
+    Ok(present(
+        tickets::Column::Priority,
+        tickets::Column::Priority.eq(priority),
+    ))
+}
+
+fn status(value: &Value) -> Result<Condition, QueryError> {
+    let status = TicketStatus::try_from_value(&value.text.to_lowercase()).map_err(|_err| {
+        error(
+            value.start,
+            format!(
+                "unknown status {:?}, expected open, in_progress, blocked, done or cancelled",
+                value.text
+            ),
+        )
+    })?;
+
+    Ok(Condition::all().add(tickets::Column::Status.eq(status)))
+}
+
+fn assignee(value: &Value, user: &AuthUser) -> Condition {
+    let assigned = Query::select()
+        .column(ticket_assignees::Column::TicketId)
+        .from(ticket_assignees::Entity)
+        .to_owned();
+
+    if value.text.eq_ignore_ascii_case("none") {
+        return Condition::all().add(tickets::Column::Id.not_in_subquery(assigned));
+    }
+
+    Condition::all().add(
+        tickets::Column::Id.in_subquery(
+            assigned
+                .to_owned()
+                .and_where(user_matches(ticket_assignees::Column::UserId, value, user))
+                .to_owned(),
+        ),
+    )
+}
+
+fn reporter(value: &Value, user: &AuthUser) -> Condition {
+    if value.text.eq_ignore_ascii_case("none") {
+        return Condition::all().add(tickets::Column::UserId.is_null());
+    }
+
+    present(
+        tickets::Column::UserId,
+        user_matches(tickets::Column::UserId, value, user),
+    )
+}
+
+fn project(value: &Value) -> Condition {
+    present(
+        tickets::Column::ProjectId,
+        tickets::Column::ProjectId.in_subquery(
+            Query::select()
+                .column(projects::Column::Id)
+                .from(projects::Entity)
+                .and_where(projects::Column::Key.eq(value.text.to_uppercase()))
+                .to_owned(),
+        ),
+    )
+}
+
+/// `2026-01-01` covers that whole day in UTC, an RFC 3339 timestamp only
+/// that instant
+fn moment(
+    column: tickets::Column,
+    comparison: Option<Comparison>,
+    value: &Value,
+) -> Result<Condition, QueryError> {
+    if value.text.eq_ignore_ascii_case("none") && comparison.is_none() {
+        return Ok(Condition::all().add(column.is_null()));
+    }
+
+    let (start, end) = if let Ok(day) = NaiveDate::parse_from_str(&value.text, "%Y-%m-%d") {
+        let start: DateTime<FixedOffset> = day
+            .and_hms_opt(0, 0, 0)
+            .unwrap_or_default()
+            .and_utc()
+            .fixed_offset();
+        (start, Some(start + Duration::days(1)))
+    } else if let Ok(instant) = DateTime::parse_from_rfc3339(&value.text) {
+        (instant, None)
+    } else {
+        return Err(error(
+            value.start,
+            format!(
+                "invalid date {:?}, expected YYYY-MM-DD or an RFC 3339 timestamp",
+                value.text
+            ),
+        ));
+    };
+
+    let condition = match (comparison, end) {
+        (None, Some(end)) => Condition::all().add(column.gte(start)).add(column.lt(end)),
+        (None, None) => Condition::all().add(column.eq(start)),
+        (Some(Comparison::After), Some(end)) => Condition::all().add(column.gte(end)),
+        (Some(Comparison::After), None) => Condition::all().add(column.gt(start)),
+        (Some(Comparison::AtOrAfter), _) => Condition::all().add(column.gte(start)),
+        (Some(Comparison::Before), _) => Condition::all().add(column.lt(start)),
+        (Some(Comparison::AtOrBefore), Some(end)) => Condition::all().add(column.lt(end)),
+        (Some(Comparison::AtOrBefore), None) => Condition::all().add(column.lte(start)),
+    };
+
+    Ok(present(column, condition))
+}
+
+impl Term {
+    fn compile(self, user: &AuthUser) -> Result<Condition, QueryError> {
+        let condition = match self.kind {
+            TermKind::Text(word) => Condition::all().add(title_contains(&word.text)),
+            TermKind::Field {
+                name,
+                comparison,
+                values,
+            } => {
+                let is_date = matches!(name.text.as_str(), "created" | "completed");
+                if let Some((_, start)) = comparison.filter(|_| !is_date) {
+                    return Err(error(
+                        start,
+                        format!(
+                            "{} cannot be compared, only created and completed can",
+                            name.text
+                        ),
+                    ));
+                }
+
+                // Several comma-separated values match any of them
+                let mut any = Condition::any();
+                for value in &values {
+                    any = any.add(match name.text.as_str() {
+                        "priority" => priority(value)?,
+                        "status" => status(value)?,
+                        "assignee" => assignee(value, user),
+                        "reporter" => reporter(value, user),
+                        "label" => {
+                            ticket_labels::labelled(slice::from_ref(&value.text), LabelMatch::Any)
+                        }
+                        "project" => project(value),
+                        "title" => Condition::all().add(title_contains(&value.text)),
+                        "created" => moment(
+                            tickets::Column::CreatedAt,
+                            comparison.map(|(comparison, _)| comparison),
+                            value,
+                        )?,
+                        "completed" => moment(
+                            tickets::Column::CompletedAt,
+                            comparison.map(|(comparison, _)| comparison),
+                            value,
+                        )?,
+                        other => {
+                            return Err(error(
+                                name.start,
+                                format!("unknown field {other:?}, expected one of {FIELDS}"),
+                            ));
+                        }
+                    });
+                }
+                any
+            }
+        };
+
+        Ok(if self.negated {
+            condition.not()
+        } else {
+            condition
+        })
+    }
+}
+
+/// Compiles a query like `priority:high status:open,blocked assignee:me
+/// created:>2026-01-01 -label:wontfix` into a condition on tickets
+///
+/// Terms are ANDed, a leading `-` negates one. `me` names the caller in
+/// `assignee` and `reporter`, and `none` matches tickets without a value.
+pub fn compile(input: &str, user: &AuthUser) -> Result<Condition, QueryError> {
+    let mut condition = Condition::all();
+
+    for term in Parser::new(input).parse()? {
+        condition = condition.add(term.compile(user)?);
+    }
+
+    Ok(condition)
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DbBackend, EntityTrait, QueryFilter, QueryTrait};
+
+    use super::*;
+    use crate::database::sea_orm_active_enums::UserRole;
+
+    const CALLER: AuthUser = AuthUser {
+        id: 42,
+        role: UserRole::Member,
+        session_id: 7,
+    };
+
+    fn sql(input: &str) -> String {
+        let condition = compile(input, &CALLER).expect("query compiles");
+        tickets::Entity::find()
+            .filter(condition)
+            .build(DbBackend::Postgres)
+            .to_string()
+    }
+
+    /// The part of the statement after `WHERE`, without identifier quotes
+    fn filter(input: &str) -> String {
+        let sql = sql(input);
+        sql.split_once(" WHERE ")
+            .map(|(_, filter)| filter.replace('"', ""))
+            .expect("statement has a filter")
+    }
+
+    fn fails_at(input: &str) -> (usize, String) {
+        let err = compile(input, &CALLER).expect_err("query is rejected");
+        (err.column, err.reason)
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert_eq!(filter(""), "TRUE");
+        assert_eq!(filter("   "), "TRUE");
+    }
+
+    #[test]
+    fn unterminated_quote_points_at_the_quote() {
+        assert_eq!(
+            fails_at(r#"title:"no end"#),
+            (7, "unterminated quote".to_owned())
+        );
+        assert_eq!(fails_at(r#"status:open "dangling"#).0, 13);
+    }
+
+    #[test]
+    fn dangling_operator_expects_a_value() {
+        assert_eq!(fails_at("created:>"), (10, "expected a value".to_owned()));
+        assert_eq!(
+            fails_at("status:open,"),
+            (13, "expected a value".to_owned())
+        );
+        assert_eq!(
+            fails_at("priority: high"),
+            (10, "expected a value".to_owned())
+        );
+    }
+
+    #[test]
+    fn unknown_field_points_at_its_name() {
+        let (column, reason) = fails_at("status:open -colour:red");
+        assert_eq!(column, 14);
+        assert!(reason.starts_with("unknown field \"colour\""), "{reason}");
+    }
+
+    #[test]
+    fn unknown_values_point_at_the_value() {
+        let (column, reason) = fails_at("status:open,someday");
+        assert_eq!(column, 13);
+        assert!(reason.starts_with("unknown status \"someday\""), "{reason}");
+
+        let (column, reason) = fails_at("created:>yesterday");
+        assert_eq!(column, 10);
+        assert!(reason.starts_with("invalid date \"yesterday\""), "{reason}");
+    }
+
+    #[test]
+    fn text_after_a_value_is_rejected() {
+        assert_eq!(
+            fails_at(r#"title:"a"b"#),
+            (10, "unexpected 'b' after value".to_owned())
+        );
+    }
+
+    #[test]
+    fn quoted_values_keep_spaces_and_commas() {
+        let filter = filter(r#"title:"needs review, soon""#);
+        assert!(filter.contains("ILIKE '%needs review, soon%'"), "{filter}");
+    }
+
+    #[test]
+    fn bare_words_match_the_title_with_like_wildcards_escaped() {
+        let filter = filter("50%_off");
+        assert!(filter.contains(r"ILIKE E'%50\\%\\_off%'"), "{filter}");
+    }
+
+    #[test]
+    fn values_are_ored_and_terms_are_anded() {
+        let filter = filter("status:open,blocked priority:high");
+        assert_eq!(
+            filter,
+            "(tickets.status = (CAST('open' AS ticket_status)) OR tickets.status = (CAST('blocked' AS ticket_status))) \
+             AND (tickets.priority IS NOT NULL AND tickets.priority = (CAST('high' AS ticket_priority)))"
+        );
+    }
+
+    #[test]
+    fn negation_applies_to_the_whole_term() {
+        let filter = filter("-status:done,cancelled priority:low");
+        assert_eq!(
+            filter,
+            "(NOT (tickets.status = (CAST('done' AS ticket_status)) OR tickets.status = (CAST('cancelled' AS ticket_status)))) \
+             AND (tickets.priority IS NOT NULL AND tickets.priority = (CAST('low' AS ticket_priority)))"
+        );
+    }
+
+    #[test]
+    fn a_lone_dash_is_a_word() {
+        let filter = filter("a - b");
+        assert!(filter.contains("ILIKE '%-%'"), "{filter}");
+        assert!(!filter.contains("NOT"), "{filter}");
+    }
+
+    #[test]
+    fn negated_nullable_field_keeps_rows_without_a_value() {
+        let filter = filter("-priority:high");
+        assert_eq!(
+            filter,
+            "NOT (tickets.priority IS NOT NULL AND tickets.priority = (CAST('high' AS ticket_priority)))"
+        );
+    }
+
+    #[test]
+    fn dates_compare_by_whole_days() {
+        assert!(filter("created:>2026-01-01").contains("created_at >= '2026-01-02 00:00:00"));
+        assert!(filter("created:>=2026-01-01").contains("created_at >= '2026-01-01 00:00:00"));
+        assert!(filter("created:<2026-01-01").contains("created_at < '2026-01-01 00:00:00"));
+        assert!(filter("completed:<=2026-01-01").contains("completed_at < '2026-01-02 00:00:00"));
+
+        let day = filter("created:2026-01-01");
+        assert!(day.contains("created_at >= '2026-01-01 00:00:00"), "{day}");
+        assert!(day.contains("created_at < '2026-01-02 00:00:00"), "{day}");
+    }
+
+    #[test]
+    fn timestamps_compare_by_instant() {
+        let filter = filter("created:>2026-01-01T12:00:00Z");
+        assert!(
+            filter.contains("created_at > '2026-01-01 12:00:00"),
+            "{filter}"
+        );
+    }
+
+    #[test]
+    fn priority_cannot_be_compared() {
+        assert_eq!(
+            fails_at("status:open priority:>high"),
+            (
+                22,
+                "priority cannot be compared, only created and completed can".to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn priority_accepts_letter_grades_and_none() {
+        assert!(filter("priority:A").contains("priority = (CAST('urgent' AS ticket_priority))"));
+        assert_eq!(filter("priority:none"), "tickets.priority IS NULL");
+    }
+
+    #[test]
+    fn me_is_the_caller() {
+        let assignee = filter("assignee:me");
+        assert!(assignee.contains("user_id = 42"), "{assignee}");
+
+        let reporter = filter("reporter:ME");
+        assert!(reporter.contains("tickets.user_id = 42"), "{reporter}");
+    }
+
+    #[test]
+    fn other_users_by_id_or_name() {
+        assert!(filter("reporter:7").contains("tickets.user_id = 7"));
+
+        let by_name = filter("assignee:bob");
+        assert!(by_name.contains("username = 'bob'"), "{by_name}");
+        assert!(!by_name.contains("= 42"), "{by_name}");
+    }
+}
@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+
+use axum::{Extension, Json, extract::State, http::StatusCode};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, Order, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, sea_query::Expr,
+};
+use serde::{Deserialize, Serialize};
+
+use super::ticket_get::ResponseTicket;
+use crate::{
+    database::{
+        ticket_comments::{self, Entity as TicketComments},
+        tickets::{self, Entity as Tickets},
+    },
+    middleware::guard::AuthUser,
+    policy::ticket as ticket_policy,
+    utils::{api_error::ApiError, extract::Query},
+};
+
+const DEFAULT_PAGE_LIMIT: u64 = 20;
+const MAX_PAGE_LIMIT: u64 = 100;
+const MAX_QUERY_LENGTH: usize = 256;
+
+/// Must match the configuration the `search` columns are generated with
+const TSQUERY: &str = "to_tsquery('english', $1)";
+
+/// Matches are wrapped in `<mark>`, see [`escaped`] for the text around them
+const HEADLINE_TITLE: &str = "'StartSel=<mark>, StopSel=</mark>, HighlightAll=true'";
+const HEADLINE_SNIPPET: &str =
+    "'StartSel=<mark>, StopSel=</mark>, MaxWords=35, MinWords=15, MaxFragments=2'";
+
+/// SQL for the HTML-escaped text of `column`
+///
+/// Highlights are meant to be inserted as markup, so everything a user
+/// wrote is escaped before `ts_headline` adds the `<mark>` tags, and those
+/// are the only tags a highlight can contain.
+fn escaped(column: &str) -> String {
+    format!(
+        "replace(replace(replace(replace(replace({column}, \
+         '&', '&amp;'), '<', '&lt;'), '>', '&gt;'), '\"', '&quot;'), '''', '&#39;')"
+    )
+}
+
+#[derive(Deserialize)]
+pub struct SearchParams {
+    /// Words to look for, `"quoted words"` match as a phrase and `word*` as a prefix
+    q: String,
+    limit: Option<u64>,
+    #[serde(default)]
+    offset: u64,
+}
+
+#[derive(Serialize)]
+pub struct ResponseCommentHighlight {
+    id: i32,
+    snippet: String,
+}
+
+/// HTML fragments with the user's text escaped and matches in `<mark>`
+#[derive(Serialize)]
+pub struct ResponseHighlights {
+    title: String,
+    description: Option<String>,
+    /// Best matching comment, if any comment matched
+    comment: Option<ResponseCommentHighlight>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseSearchHit {
+    ticket: ResponseTicket,
+    rank: f32,
+    highlights: ResponseHighlights,
+}
+
+#[derive(Serialize)]
+pub struct ResponseTicketSearch {
+    hits: Vec<ResponseSearchHit>,
+    total: u64,
+    limit: u64,
+    offset: u64,
+}
+
+/// Turns what a user typed into a `to_tsquery` expression
+///
+/// Words are ANDed, `"quoted words"` have to follow each other and a
+/// trailing `*` matches every word with that prefix. Anything but letters
+/// and digits only separates words, so the input can never form tsquery
+/// operators of its own.
+fn build_tsquery(input: &str) -> Option<String> {
+    let mut terms = Vec::new();
+
+    for (index, segment) in input.split('"').enumerate() {
+        // Odd segments sit between a pair of quotes
+        if index % 2 == 1 {
+            terms.extend(phrase(segment));
+        } else {
+            terms.extend(segment.split_whitespace().filter_map(phrase));
+        }
+    }
+
+    (!terms.is_empty()).then(|| terms.join(" & "))
+}
+
+/// Words that have to appear in this order, the last one as a prefix when
+/// it ends in `*`
+fn phrase(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let last = words.last()?;
+
+    let prefix = if text.trim_end().ends_with('*') {
+        ":*"
+    } else {
+        ""
+    };
+
+    Some(match words.as_slice() {
+        [word] => format!("{word}{prefix}"),
+        _ => format!(
+            "({} <-> {last}{prefix})",
+            words[..words.len() - 1].join(" <-> ")
+        ),
+    })
+}
+
+/// Highlighted snippet of the best matching comment on each of the tickets
+async fn comment_highlights(
+    db_conn: &DatabaseConnection,
+    ticket_ids: &[i32],
+    tsquery: &str,
+) -> Result<HashMap<i32, ResponseCommentHighlight>, ApiError> {
+    let rows: Vec<(i32, i32, String)> = TicketComments::find()
+        .select_only()
+        .column(ticket_comments::Column::TicketId)
+        .column(ticket_comments::Column::Id)
+        .expr_as(
+            Expr::cust_with_values(
+                format!(
+                    "ts_headline('english', {}, {TSQUERY}, {HEADLINE_SNIPPET})",
+                    escaped("\"body\"")
+                ),
+                [tsquery],
+            ),
+            "snippet",
+        )
+        .distinct_on([ticket_comments::Column::TicketId])
+        .filter(ticket_comments::Column::TicketId.is_in(ticket_ids.iter().copied()))
+        .filter(ticket_comments::Column::DeletedAt.is_null())
+        .filter(Expr::cust_with_values(
+            format!("\"search\" @@ {TSQUERY}"),
+            [tsquery],
+        ))
+        .order_by_asc(ticket_comments::Column::TicketId)
+        .order_by(
+            Expr::cust_with_values(format!("ts_rank_cd(\"search\", {TSQUERY})"), [tsquery]),
+            Order::Desc,
+        )
+        .order_by_asc(ticket_comments::Column::Id)
+        .into_tuple()
+        .all(db_conn)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(ticket_id, id, snippet)| (ticket_id, ResponseCommentHighlight { id, snippet }))
+        .collect())
+}
+
+/// Searches titles, descriptions and comments of the tickets the caller can see
+///
+/// Hits are ranked by cover density, title matches weigh more than
+/// description matches and comment matches count for half.
+pub async fn ticket_search(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<ResponseTicketSearch>, ApiError> {
+    if params.q.chars().count() > MAX_QUERY_LENGTH {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("Search query must be at most {MAX_QUERY_LENGTH} characters"),
+        )
+        .with_code("invalid_search"));
+    }
+
+    let tsquery = build_tsquery(&params.q).ok_or_else(|| {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "Search query needs at least one word",
+        )
+        .with_code("invalid_search")
+    })?;
+
+    let matching_comment = format!(
+        "FROM \"ticket_comments\" \
+         WHERE \"ticket_comments\".\"ticket_id\" = \"tickets\".\"id\" \
+         AND \"ticket_comments\".\"deleted_at\" IS NULL \
+         AND \"ticket_comments\".\"search\" @@ {TSQUERY}"
+    );
+
+    let query = Tickets::find()
+        .filter(ticket_policy::visible_to(&user, false))
+        .filter(Expr::cust_with_values(
+            format!(
+                "(\"tickets\".\"search\" @@ {TSQUERY} OR EXISTS (SELECT 1 {matching_comment}))"
+            ),
+            [tsquery.as_str()],
+        ));
+
+    let total = query.clone().count(&db_conn).await.map_err(|err| {
+        tracing::error!("Error: {:?}", err);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error searching tickets")
+    })?;
+
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .min(MAX_PAGE_LIMIT);
+
+    let rank = Expr::cust_with_values(
+        format!(
+            "(ts_rank_cd(\"tickets\".\"search\", {TSQUERY}) + coalesce((\
+             SELECT max(ts_rank_cd(\"ticket_comments\".\"search\", {TSQUERY})) \
+             {matching_comment}), 0) / 2)::real"
+        ),
+        [tsquery.as_str()],
+    );
+
+    let mut rows: Vec<(i32, f32, String, Option<String>)> = query
+        .select_only()
+        .column(tickets::Column::Id)
+        .expr_as(rank, "rank")
+        .expr_as(
+            Expr::cust_with_values(
+                format!(
+                    "ts_headline('english', {}, {TSQUERY}, {HEADLINE_TITLE})",
+                    escaped("\"tickets\".\"title\"")
+                ),
+                [tsquery.as_str()],
+            ),
+            "title_highlight",
+        )
+        .expr_as(
+            Expr::cust_with_values(
+                format!(
+                    "ts_headline('english', {}, {TSQUERY}, {HEADLINE_SNIPPET})",
+                    escaped("\"tickets\".\"description\"")
+                ),
+                [tsquery.as_str()],
+            ),
+            "description_highlight",
+        )
+        .order_by(Expr::cust("\"rank\""), Order::Desc)
+        .order_by_asc(tickets::Column::Id)
+        .limit(limit)
+        .offset(params.offset)
+        .into_tuple()
+        .all(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error searching tickets")
+        })?;
+
+    let mut models: HashMap<i32, tickets::Model> = Tickets::find()
+        .filter(tickets::Column::Id.is_in(rows.iter().map(|(id, ..)| *id)))
+        .all(&db_conn)
+        .await?
+        .into_iter()
+        .map(|ticket| (ticket.id, ticket))
+        .collect();
+    // A ticket purged since the search ran has nothing left to show
+    rows.retain(|(id, ..)| models.contains_key(id));
+
+    let ticket_ids: Vec<i32> = rows.iter().map(|(id, ..)| *id).collect();
+    // Keep the rank order of the hits
+    let models = ticket_ids
+        .iter()
+        .filter_map(|id| models.remove(id))
+        .collect();
+
+    let tickets = ResponseTicket::load_many(&db_conn, models).await?;
+    let mut comments = comment_highlights(&db_conn, &ticket_ids, &tsquery).await?;
+
+    let hits = tickets
+        .into_iter()
+        .zip(rows)
+        .map(
+            |(ticket, (id, rank, title, description))| ResponseSearchHit {
+                ticket,
+                rank,
+                highlights: ResponseHighlights {
+                    title,
+                    description,
+                    comment: comments.remove(&id),
+                },
+            },
+        )
+        .collect();
+
+    Ok(Json(ResponseTicketSearch {
+        hits,
+        total,
+        limit,
+        offset: params.offset,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_are_anded() {
+        assert_eq!(
+            build_tsquery("Login  broken").as_deref(),
+            Some("login & broken")
+        );
+    }
+
+    #[test]
+    fn empty_input_has_no_query() {
+        assert_eq!(build_tsquery(""), None);
+        assert_eq!(build_tsquery("   "), None);
+        assert_eq!(build_tsquery("\"\""), None);
+        assert_eq!(build_tsquery("& | ! * :"), None);
+    }
+
+    #[test]
+    fn quoted_words_form_a_phrase() {
+        assert_eq!(
+            build_tsquery("\"reset password link\" mail").as_deref(),
+            Some("(reset <-> password <-> link) & mail")
+        );
+    }
+
+    #[test]
+    fn an_unclosed_quote_runs_to_the_end() {
+        assert_eq!(
+            build_tsquery("mail \"reset password").as_deref(),
+            Some("mail & (reset <-> password)")
+        );
+    }
+
+    #[test]
+    fn trailing_star_is_a_prefix() {
+        assert_eq!(build_tsquery("log*").as_deref(), Some("log:*"));
+        assert_eq!(
+            build_tsquery("\"password res*\"").as_deref(),
+            Some("(password <-> res:*)")
+        );
+        assert_eq!(build_tsquery("*log").as_deref(), Some("log"));
+    }
+
+    #[test]
+    fn user_text_cannot_form_operators() {
+        assert_eq!(
+            build_tsquery("a|b !c & (d) <-> e <2> f").as_deref(),
+            Some("(a <-> b) & c & d & e & 2 & f")
+        );
+        assert_eq!(build_tsquery("log:*").as_deref(), Some("log:*"));
+        assert_eq!(build_tsquery("log:A").as_deref(), Some("(log <-> a)"));
+        assert_eq!(build_tsquery("it's").as_deref(), Some("(it <-> s)"));
+        assert_eq!(
+            build_tsquery(r"back\slash \' \\").as_deref(),
+            Some("(back <-> slash)")
+        );
+    }
+
+    #[test]
+    fn phrase_only_keeps_letters_and_digits() {
+        assert_eq!(phrase("  "), None);
+        assert_eq!(phrase("':*'"), None);
+        assert_eq!(phrase("Été 2026").as_deref(), Some("(été <-> 2026)"));
+        assert_eq!(phrase("x-ray*").as_deref(), Some("(x <-> ray:*)"));
+    }
+}
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use axum::{
+    Extension, Json,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use axum_extra::{TypedHeader, headers::ETag};
+use chrono::Utc;
+use sea_orm::{
+    ActiveEnum, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, TransactionTrait,
+    prelude::Expr,
+};
+use serde::Deserialize;
+use validator::Validate;
+
+use super::{
+    ticket_assignees::assignee_ids, ticket_get::ResponseTicket, ticket_history::record_changes,
+};
+use crate::{
+    config::Config,
+    database::{
+        sea_orm_active_enums::TicketStatus,
+        tickets::{self, Entity as Tickets},
+    },
+    middleware::guard::AuthUser,
+    policy::ticket::{self as ticket_policy, TicketAction},
+    utils::{
+        api_error::ApiError,
+        extract::Path,
+        precondition::{check_if_match, ticket_changed, ticket_etag},
+        validation::ValidatedJson,
+    },
+};
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RequestTransition {
+    status: TicketStatus,
+}
+
+/// Moves a ticket to another status along the configured workflow
+///
+/// `completed_at` follows the status, it is set when the ticket becomes done
+/// and cleared when it leaves done
+pub async fn ticket_transition(
+    State(db_conn): State<DatabaseConnection>,
+    State(config): State<Arc<Config>>,
+    Extension(user): Extension<AuthUser>,
+    Path(ticket_id): Path<i32>,
+    headers: HeaderMap,
+    ValidatedJson(req_transition): ValidatedJson<RequestTransition>,
+) -> Result<(TypedHeader<ETag>, Json<ResponseTicket>), ApiError> {
+    let ticket = Tickets::find_by_id(ticket_id)
+        .one(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch ticket")
+        })?
+        .filter(|ticket| ticket.deleted_at.is_none())
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No ticket found"))?;
+
+    let assignees = assignee_ids(&db_conn, ticket.id).await?;
+    ticket_policy::authorize(&user, &ticket, &assignees, TicketAction::Edit)?;
+    check_if_match(
+        &headers,
+        &ticket_etag(ticket.version),
+        config.tickets.require_if_match,
+    )?;
+
+    let from = ticket.status;
+    let to = req_transition.status;
+
+    if !config.workflow.allows(from, to) {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            format!(
+                "Cannot move ticket from {} to {}",
+                from.to_value(),
+                to.to_value()
+            ),
+        )
+        .with_code("invalid_transition"));
+    }
+
+    let completed_at = (to == TicketStatus::Done).then(|| Utc::now().fixed_offset());
+
+    let txn = db_conn.begin().await?;
+
+    // Only move the ticket if nobody changed it since we read it
+    let moved = Tickets::update_many()
+        .col_expr(
+            tickets::Column::Status,
+            tickets::Column::Status.save_as(Expr::val(to)),
+        )
+        .col_expr(tickets::Column::CompletedAt, Expr::value(completed_at))
+        .col_expr(
+            tickets::Column::Version,
+            Expr::col(tickets::Column::Version).add(1),
+        )
+        .filter(tickets::Column::Id.eq(ticket.id))
+        .filter(tickets::Column::Version.eq(ticket.version))
+        .exec_with_returning(&txn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to update ticket")
+        })?
+        .pop()
+        .ok_or_else(ticket_changed)?;
+
+    record_changes(&txn, user.id, &ticket, &moved).await?;
+    txn.commit().await?;
+
+    tracing::info!(
+        "Ticket {:?} moved from {} to {} by User {:?}",
+        moved.id,
+        from.to_value(),
+        to.to_value(),
+        user.id
+    );
+    let etag = ticket_etag(moved.version);
+    Ok((
+        TypedHeader(etag),
+        Json(ResponseTicket::load(&db_conn, moved, assignees).await?),
+    ))
+}
@@ -1,57 +1,120 @@
+use std::sync::Arc;
+
 use axum::{
-    Json,
-    extract::{Path, State},
-    http::StatusCode,
+    Extension,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::Response,
 };
+use axum_extra::TypedHeader;
 use sea_orm::{
-    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set, prelude::DateTimeWithTimeZone,
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set, TransactionTrait,
+    prelude::DateTimeWithTimeZone,
 };
 use serde::Deserialize;
+use validator::Validate;
 
+use super::{
+    ticket_assignees::assignee_ids, ticket_get::ResponseTicket, ticket_history::record_changes,
+};
 use crate::{
-    database::tickets::{self, Entity as Tickets},
-    utils::api_error::ApiError,
+    config::Config,
+    database::{
+        sea_orm_active_enums::TicketPriority,
+        tickets::{self, Entity as Tickets},
+    },
+    middleware::guard::AuthUser,
+    policy::ticket::{self as ticket_policy, TicketAction},
+    utils::{
+        api_error::ApiError,
+        extract::Path,
+        precondition::{check_if_match, ticket_changed, ticket_etag},
+        prefer::written,
+        validation::{ValidatedJson, not_blank},
+    },
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 pub struct RequestTask {
     #[allow(dead_code)]
     id: Option<i32>,
-    priority: Option<String>,
+    priority: Option<TicketPriority>,
+    #[validate(
+        custom(function = "not_blank"),
+        length(max = 255, message = "must be at most 255 characters")
+    )]
     title: String,
-    completed_at: Option<DateTimeWithTimeZone>,
+    #[validate(length(max = 10000, message = "must be at most 10000 characters"))]
     description: Option<String>,
     deleted_at: Option<DateTimeWithTimeZone>,
-    user_id: Option<i32>,
-    in_progress: Option<bool>,
 }
 
 pub async fn ticket_update_atomic(
     State(db_conn): State<DatabaseConnection>,
+    State(config): State<Arc<Config>>,
+    Extension(user): Extension<AuthUser>,
     Path(ticket_id): Path<i32>,
-    Json(req_ticket): Json<RequestTask>,
-) -> Result<(), ApiError> {
+    headers: HeaderMap,
+    ValidatedJson(req_ticket): ValidatedJson<RequestTask>,
+) -> Result<Response, ApiError> {
     tracing::info!("Attempting to update ticket {:?}", &ticket_id);
+    let db_ticket = Tickets::find_by_id(ticket_id)
+        .one(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch ticket")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No ticket found"))?;
+
+    let assignees = assignee_ids(&db_conn, db_ticket.id).await?;
+    ticket_policy::authorize(&user, &db_ticket, &assignees, TicketAction::Edit)?;
+    if req_ticket.deleted_at != db_ticket.deleted_at {
+        ticket_policy::authorize(&user, &db_ticket, &assignees, TicketAction::Delete)?;
+    }
+
+    check_if_match(
+        &headers,
+        &ticket_etag(db_ticket.version),
+        config.tickets.require_if_match,
+    )?;
+
     let atomic_ticket = tickets::ActiveModel {
         id: Set(ticket_id),
         priority: Set(req_ticket.priority),
         title: Set(req_ticket.title),
-        completed_at: Set(req_ticket.completed_at),
         description: Set(req_ticket.description),
         deleted_at: Set(req_ticket.deleted_at),
-        user_id: Set(req_ticket.user_id),
-        in_progress: Set(req_ticket.in_progress),
+        version: Set(db_ticket.version + 1),
+        // The reporter never changes, assignees and status have their own endpoints
+        ..Default::default()
     };
 
-    Tickets::update(atomic_ticket)
+    let txn = db_conn.begin().await?;
+
+    let ticket = Tickets::update(atomic_ticket)
         .filter(tickets::Column::Id.eq(ticket_id))
-        .exec(&db_conn)
+        // Someone else wrote the ticket since it was read above
+        .filter(tickets::Column::Version.eq(db_ticket.version))
+        .exec(&txn)
         .await
-        .map_err(|err| {
-            tracing::error!("Error: {:?}", err);
-            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to update ticket")
+        .map_err(|err| match err {
+            DbErr::RecordNotUpdated => ticket_changed(),
+            err => {
+                tracing::error!("Error: {:?}", err);
+                ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to update ticket")
+            }
         })?;
 
+    record_changes(&txn, user.id, &db_ticket, &ticket).await?;
+    txn.commit().await?;
+
     tracing::info!("Ticket updated");
-    Ok(())
+    let etag = TypedHeader(ticket_etag(ticket.version));
+    Ok(written(
+        &headers,
+        StatusCode::OK,
+        etag,
+        ResponseTicket::load(&db_conn, ticket, assignees).await?,
+    ))
 }
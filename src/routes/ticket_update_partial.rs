@@ -1,96 +1,429 @@
-use axum::{Extension, Json, extract::Path, http::StatusCode};
+use std::sync::Arc;
+
+use axum::{
+    Extension,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::Response,
+};
+use axum_extra::TypedHeader;
+use json_patch::{Patch, PatchErrorKind};
 use sea_orm::{
-    ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, Set,
-    prelude::DateTimeWithTimeZone,
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel, QueryFilter, Set,
+    TransactionTrait, prelude::DateTimeWithTimeZone,
 };
-use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use validator::Validate;
 
+use super::{
+    ticket_assignees::assignee_ids, ticket_get::ResponseTicket, ticket_history::record_changes,
+};
 use crate::{
-    database::tickets::{self, Entity as Tickets},
-    utils::api_error::ApiError,
+    config::Config,
+    database::{
+        sea_orm_active_enums::TicketPriority,
+        tickets::{self, Entity as Tickets},
+    },
+    middleware::guard::AuthUser,
+    policy::ticket::{self as ticket_policy, TicketAction},
+    utils::{
+        api_error::{ApiError, FieldError},
+        extract::Path,
+        precondition::{check_if_match, ticket_changed, ticket_etag},
+        prefer::written,
+        validation::{collect_field_errors, invalid_body, not_blank},
+    },
 };
 
-#[derive(Deserialize)]
-pub struct RequestTicket {
-    #[allow(dead_code)]
-    id: Option<i32>,
-    #[serde(
-        default,
-        skip_serializing_if = "Option::is_none",
-        with = "::serde_with::rust::double_option"
-    )]
-    priority: Option<Option<String>>,
-    title: Option<String>,
-    #[serde(
-        default,
-        skip_serializing_if = "Option::is_none",
-        with = "::serde_with::rust::double_option"
-    )]
-    completed_at: Option<Option<DateTimeWithTimeZone>>,
-    #[serde(
-        default,
-        skip_serializing_if = "Option::is_none",
-        with = "::serde_with::rust::double_option"
-    )]
-    description: Option<Option<String>>,
-    #[serde(
-        default,
-        skip_serializing_if = "Option::is_none",
-        with = "::serde_with::rust::double_option"
-    )]
-    deleted_at: Option<Option<DateTimeWithTimeZone>>,
-    #[serde(
-        default,
-        skip_serializing_if = "Option::is_none",
-        with = "::serde_with::rust::double_option"
+const MERGE_PATCH: &str = "application/merge-patch+json";
+const JSON_PATCH: &str = "application/json-patch+json";
+
+/// Members of the ticket representation a patch may change, the others are
+/// read-only or have endpoints of their own
+const EDITABLE_FIELDS: [&str; 4] = ["title", "description", "priority", "deleted_at"];
+
+/// Editable part of a ticket once the patch is applied
+#[derive(Validate, Debug)]
+struct PatchedTicket {
+    #[validate(
+        custom(function = "not_blank"),
+        length(max = 255, message = "must be at most 255 characters")
     )]
-    in_progress: Option<Option<bool>>,
+    title: String,
+    #[validate(length(max = 10000, message = "must be at most 10000 characters"))]
+    description: Option<String>,
+    priority: Option<TicketPriority>,
+    deleted_at: Option<DateTimeWithTimeZone>,
+}
+
+enum TicketPatch {
+    /// RFC 7396, plain `application/json` bodies are read this way too
+    Merge(Value),
+    /// RFC 6902
+    Json(Patch),
+}
+
+impl TicketPatch {
+    fn from_request(headers: &HeaderMap, body: &Bytes) -> Result<Self, ApiError> {
+        let unsupported = || {
+            ApiError::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("Send the patch as {MERGE_PATCH} or {JSON_PATCH}"),
+            )
+        };
+
+        let media_type = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .and_then(|content_type| content_type.split(';').next())
+            .map(|media_type| media_type.trim().to_ascii_lowercase())
+            .ok_or_else(unsupported)?;
+
+        match media_type.as_str() {
+            MERGE_PATCH | "application/json" => parse_body(body).map(TicketPatch::Merge),
+            JSON_PATCH => parse_body(body).map(TicketPatch::Json),
+            _ => Err(unsupported()),
+        }
+    }
+
+    fn apply(&self, document: &mut Value) -> Result<(), ApiError> {
+        match self {
+            TicketPatch::Merge(patch) => {
+                json_patch::merge(document, patch);
+                Ok(())
+            }
+            TicketPatch::Json(patch) => json_patch::patch(document, patch).map_err(|err| {
+                let detail = format!("Patch failed: {err}");
+                match err.kind {
+                    PatchErrorKind::TestFailed => {
+                        ApiError::new(StatusCode::CONFLICT, detail).with_code("patch_test_failed")
+                    }
+                    _ => ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, detail)
+                        .with_code("invalid_patch"),
+                }
+            }),
+        }
+    }
+}
+
+/// Same split as `Json` rejections, broken JSON is a 400 and JSON of the
+/// wrong shape a 422
+fn parse_body<T: DeserializeOwned>(body: &Bytes) -> Result<T, ApiError> {
+    serde_json::from_slice(body).map_err(|err| {
+        if err.is_syntax() || err.is_eof() {
+            ApiError::new(StatusCode::BAD_REQUEST, format!("Malformed JSON: {err}"))
+                .with_code("malformed_json")
+        } else {
+            ApiError::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Invalid patch document: {err}"),
+            )
+            .with_code("invalid_body")
+        }
+    })
 }
 
+fn patched_field<T: DeserializeOwned>(
+    document: &Map<String, Value>,
+    field: &str,
+    errors: &mut Vec<FieldError>,
+) -> Option<T> {
+    let value = document.get(field).cloned().unwrap_or(Value::Null);
+    serde_json::from_value(value)
+        .map_err(|err| {
+            errors.push(FieldError {
+                field: field.to_owned(),
+                reason: err.to_string(),
+            })
+        })
+        .ok()
+}
+
+/// Reads the editable fields back out of the patched representation, after
+/// making sure the patch left everything else alone
+fn read_patched(original: &Value, patched: &Value) -> Result<PatchedTicket, ApiError> {
+    let empty = Map::new();
+    let original = original.as_object().unwrap_or(&empty);
+    let Some(patched) = patched.as_object() else {
+        return Err(ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Patch must leave the ticket a JSON object",
+        )
+        .with_code("invalid_patch"));
+    };
+
+    let mut errors = Vec::new();
+
+    for field in original.keys().chain(patched.keys()) {
+        if EDITABLE_FIELDS.contains(&field.as_str())
+            || original.get(field) == patched.get(field)
+            || errors
+                .iter()
+                .any(|error: &FieldError| &error.field == field)
+        {
+            continue;
+        }
+
+        let reason = if original.contains_key(field) {
+            "is read-only"
+        } else {
+            "is not a ticket field"
+        };
+        errors.push(FieldError {
+            field: field.clone(),
+            reason: reason.to_owned(),
+        });
+    }
+
+    let title = patched_field(patched, "title", &mut errors);
+    let description = patched_field(patched, "description", &mut errors);
+    let priority = patched_field(patched, "priority", &mut errors);
+    let deleted_at = patched_field(patched, "deleted_at", &mut errors);
+
+    let (Some(title), Some(description), Some(priority), Some(deleted_at)) =
+        (title, description, priority, deleted_at)
+    else {
+        return Err(invalid_body(errors));
+    };
+
+    let ticket = PatchedTicket {
+        title,
+        description,
+        priority,
+        deleted_at,
+    };
+
+    if let Err(validation_errors) = ticket.validate() {
+        collect_field_errors(&validation_errors, None, &mut errors);
+    }
+    if !errors.is_empty() {
+        return Err(invalid_body(errors));
+    }
+
+    Ok(ticket)
+}
+
+/// Applies a merge patch or JSON patch to the ticket as served by `GET /tickets/{id}`
 pub async fn ticket_update_partial(
-    Extension(db_conn): Extension<DatabaseConnection>,
+    State(db_conn): State<DatabaseConnection>,
+    State(config): State<Arc<Config>>,
+    Extension(user): Extension<AuthUser>,
     Path(ticket_id): Path<i32>,
-    Json(request_ticket): Json<RequestTicket>,
-) -> Result<(), ApiError> {
-    let mut db_ticket = if let Some(ticket) = Tickets::find_by_id(ticket_id)
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ApiError> {
+    let patch = TicketPatch::from_request(&headers, &body)?;
+
+    let ticket = Tickets::find_by_id(ticket_id)
         .one(&db_conn)
         .await
-        .map_err(|_| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch ticket"))?
-    {
-        ticket.into_active_model()
-    } else {
-        return Err(ApiError::new(StatusCode::NOT_FOUND, "No ticket found"));
-    };
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch ticket")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No ticket found"))?;
 
-    if let Some(priority) = request_ticket.priority {
-        db_ticket.priority = Set(priority);
+    let assignees = assignee_ids(&db_conn, ticket.id).await?;
+    ticket_policy::authorize(&user, &ticket, &assignees, TicketAction::Edit)?;
+
+    check_if_match(
+        &headers,
+        &ticket_etag(ticket.version),
+        config.tickets.require_if_match,
+    )?;
+
+    let original = ResponseTicket::load(&db_conn, ticket.clone(), assignees.clone()).await?;
+    let original = serde_json::to_value(original).map_err(|err| {
+        tracing::error!("Error: {:?}", err);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to update ticket")
+    })?;
+    let mut patched = original.clone();
+    patch.apply(&mut patched)?;
+
+    let patched = read_patched(&original, &patched)?;
+
+    if patched.deleted_at != ticket.deleted_at {
+        ticket_policy::authorize(&user, &ticket, &assignees, TicketAction::Delete)?;
+    }
+
+    let mut db_ticket = ticket.clone().into_active_model();
+    db_ticket.title = Set(patched.title);
+    db_ticket.description = Set(patched.description);
+    db_ticket.priority = Set(patched.priority);
+    db_ticket.deleted_at = Set(patched.deleted_at);
+    db_ticket.version = Set(ticket.version + 1);
+
+    let txn = db_conn.begin().await?;
+
+    let updated = Tickets::update(db_ticket)
+        .filter(tickets::Column::Id.eq(ticket_id))
+        // Someone else wrote the ticket since it was read above
+        .filter(tickets::Column::Version.eq(ticket.version))
+        .exec(&txn)
+        .await
+        .map_err(|err| match err {
+            DbErr::RecordNotUpdated => ticket_changed(),
+            err => {
+                tracing::error!("Error: {:?}", err);
+                ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to update ticket")
+            }
+        })?;
+
+    record_changes(&txn, user.id, &ticket, &updated).await?;
+    txn.commit().await?;
+
+    let etag = TypedHeader(ticket_etag(updated.version));
+    Ok(written(
+        &headers,
+        StatusCode::OK,
+        etag,
+        ResponseTicket::load(&db_conn, updated, assignees).await?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn original() -> Value {
+        json!({
+            "id": 7,
+            "title": "Login broken",
+            "description": "Since the upgrade",
+            "priority": "high",
+            "status": "open",
+            "assignees": [2],
+            "deleted_at": null,
+        })
     }
 
-    if let Some(title) = request_ticket.title {
-        db_ticket.title = Set(title);
+    fn json_patch(operations: Value) -> TicketPatch {
+        TicketPatch::Json(serde_json::from_value(operations).expect("valid JSON Patch"))
     }
 
-    if let Some(completed_at) = request_ticket.completed_at {
-        db_ticket.completed_at = Set(completed_at);
+    fn patched(patch: &TicketPatch) -> Result<Value, ApiError> {
+        let mut document = original();
+        patch.apply(&mut document)?;
+        Ok(document)
     }
 
-    if let Some(description) = request_ticket.description {
-        db_ticket.description = Set(description);
+    fn rejected_fields(err: &ApiError) -> Vec<(&str, &str)> {
+        err.errors
+            .iter()
+            .map(|error| (error.field.as_str(), error.reason.as_str()))
+            .collect()
     }
 
-    if let Some(deleted_at) = request_ticket.deleted_at {
-        db_ticket.deleted_at = Set(deleted_at);
+    #[test]
+    fn merge_patch_changes_editable_fields() {
+        let patch = TicketPatch::Merge(json!({"title": "Login fixed", "description": null}));
+        let ticket = read_patched(&original(), &patched(&patch).unwrap()).unwrap();
+
+        assert_eq!(ticket.title, "Login fixed");
+        assert_eq!(ticket.description, None);
+        assert_eq!(ticket.priority, Some(TicketPriority::High));
+        assert_eq!(ticket.deleted_at, None);
     }
 
-    if let Some(in_progress) = request_ticket.in_progress {
-        db_ticket.in_progress = Set(in_progress);
+    #[test]
+    fn json_patch_changes_editable_fields() {
+        let patch = json_patch(json!([
+            {"op": "test", "path": "/title", "value": "Login broken"},
+            {"op": "replace", "path": "/priority", "value": "urgent"},
+        ]));
+        let ticket = read_patched(&original(), &patched(&patch).unwrap()).unwrap();
+
+        assert_eq!(ticket.priority, Some(TicketPriority::Urgent));
     }
 
-    Tickets::update(db_ticket)
-        .filter(tickets::Column::Id.eq(ticket_id))
-        .exec(&db_conn)
-        .await
-        .map_err(|_| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to update ticket"))?;
+    #[test]
+    fn fields_outside_the_allow_list_are_rejected() {
+        let patch = TicketPatch::Merge(json!({
+            "status": "done",
+            "assignees": [3],
+            "colour": "red",
+        }));
+        let err = read_patched(&original(), &patched(&patch).unwrap()).unwrap_err();
 
-    Ok(())
+        assert_eq!(err.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            rejected_fields(&err),
+            [
+                ("assignees", "is read-only"),
+                ("colour", "is not a ticket field"),
+                ("status", "is read-only"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unchanged_read_only_fields_are_accepted() {
+        let patch = TicketPatch::Merge(json!({"status": "open", "title": "Renamed"}));
+
+        assert!(read_patched(&original(), &patched(&patch).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn removing_a_read_only_field_is_rejected() {
+        let patch = json_patch(json!([{"op": "remove", "path": "/id"}]));
+        let err = read_patched(&original(), &patched(&patch).unwrap()).unwrap_err();
+
+        assert_eq!(rejected_fields(&err), [("id", "is read-only")]);
+    }
+
+    #[test]
+    fn patched_values_are_validated() {
+        let patch = TicketPatch::Merge(json!({"title": "  ", "priority": "someday"}));
+        let err = read_patched(&original(), &patched(&patch).unwrap()).unwrap_err();
+
+        assert_eq!(err.status, StatusCode::UNPROCESSABLE_ENTITY);
+        let fields: Vec<_> = rejected_fields(&err)
+            .into_iter()
+            .map(|(field, _)| field)
+            .collect();
+        assert_eq!(fields, ["priority"]);
+
+        let patch = TicketPatch::Merge(json!({"title": "  "}));
+        let err = read_patched(&original(), &patched(&patch).unwrap()).unwrap_err();
+        assert_eq!(rejected_fields(&err), [("title", "must not be blank")]);
+    }
+
+    #[test]
+    fn replacing_the_whole_ticket_with_a_non_object_is_rejected() {
+        let patch = json_patch(json!([{"op": "replace", "path": "", "value": "gone"}]));
+        let err = read_patched(&original(), &patched(&patch).unwrap()).unwrap_err();
+
+        assert_eq!(err.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(err.code, "invalid_patch");
+    }
+
+    #[test]
+    fn failing_test_operation_is_a_conflict() {
+        let patch = json_patch(json!([
+            {"op": "test", "path": "/title", "value": "Something else"},
+            {"op": "replace", "path": "/title", "value": "Login fixed"},
+        ]));
+        let err = patched(&patch).unwrap_err();
+
+        assert_eq!(err.status, StatusCode::CONFLICT);
+        assert_eq!(err.code, "patch_test_failed");
+    }
+
+    #[test]
+    fn other_patch_errors_are_unprocessable() {
+        for operations in [
+            json!([{"op": "remove", "path": "/nothing"}]),
+            json!([{"op": "replace", "path": "/assignees/5", "value": 1}]),
+            json!([{"op": "move", "from": "/missing", "path": "/title"}]),
+        ] {
+            let err = patched(&json_patch(operations)).unwrap_err();
+
+            assert_eq!(err.status, StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(err.code, "invalid_patch");
+        }
+    }
 }
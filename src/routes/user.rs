@@ -1,15 +1,33 @@
 use axum::{Json, extract::State, http::StatusCode};
-use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+use axum_extra::{TypedHeader, headers::UserAgent};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, Set, SqlErr};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
 
 use crate::{
+    config::Config,
     database::users::{self},
-    utils::{api_error::ApiError, hashing::argon_hash, jwt::create_jwt},
+    utils::{
+        api_error::ApiError,
+        hashing::argon_hash,
+        jwt_keys::JwtKeys,
+        session::start_session,
+        validation::{USERNAME_PATTERN, ValidatedJson},
+    },
 };
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Validate)]
 pub struct RequestUser {
+    #[validate(
+        length(min = 3, max = 64, message = "must be between 3 and 64 characters"),
+        regex(
+            path = *USERNAME_PATTERN,
+            message = "may only contain letters, digits, '_', '.' and '-'"
+        )
+    )]
     username: String,
+    #[validate(length(min = 8, max = 128, message = "must be between 8 and 128 characters"))]
     password: String,
 }
 
@@ -18,38 +36,53 @@ pub struct ResponseUser {
     id: i32,
     username: String,
     token: String,
+    refresh_token: String,
 }
 
 pub async fn create_user(
     State(db_conn): State<DatabaseConnection>,
-    Json(req_user): Json<RequestUser>,
+    State(config): State<Arc<Config>>,
+    State(jwt_keys): State<Arc<JwtKeys>>,
+    user_agent: Option<TypedHeader<UserAgent>>,
+    ValidatedJson(req_user): ValidatedJson<RequestUser>,
 ) -> Result<Json<ResponseUser>, ApiError> {
     tracing::info!("Attempting to create a new user...");
-    let jwt = create_jwt().map_err(|err| {
-        tracing::error!("Error: {:?}", err);
-        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal Error")
-    })?;
 
     let new_user = users::ActiveModel {
         username: Set(req_user.username),
         password: Set(argon_hash(req_user.password)?),
-        token: Set(Some(jwt)),
         ..Default::default()
     }
-    .save(&db_conn)
+    .insert(&db_conn)
     .await
-    .map_err(|err| {
-        tracing::error!("Error: {:?}", err);
-        ApiError::new(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Unable to create new user at this time, please try again later",
-        )
+    .map_err(|err| match err.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            ApiError::new(StatusCode::CONFLICT, "Username is already taken")
+                .with_code("username_taken")
+        }
+        _ => {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Unable to create new user at this time, please try again later",
+            )
+        }
     })?;
 
-    tracing::info!("New user created: {:?}", &new_user);
+    let tokens = start_session(
+        &db_conn,
+        &config.jwt,
+        &jwt_keys,
+        &new_user,
+        user_agent.map(|TypedHeader(agent)| agent.to_string()),
+    )
+    .await?;
+
+    tracing::info!("New user created: {:?}", &new_user.username);
     Ok(Json(ResponseUser {
-        id: new_user.id.unwrap(),
-        username: new_user.username.unwrap(),
-        token: new_user.token.unwrap().unwrap(),
+        id: new_user.id,
+        username: new_user.username,
+        token: tokens.token,
+        refresh_token: tokens.refresh_token,
     }))
 }
@@ -1,18 +1,28 @@
 use axum::{Extension, Json, extract::State, http::StatusCode};
-use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
-    Set,
-};
+use axum_extra::{TypedHeader, headers::UserAgent};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
 
 use crate::{
-    database::users::{self, Entity as Users, Model},
-    utils::{api_error::ApiError, hashing::argon_verify, jwt::create_jwt},
+    config::Config,
+    database::users::{self, Entity as Users},
+    middleware::guard::AuthUser,
+    utils::{
+        api_error::ApiError,
+        hashing::argon_verify,
+        jwt_keys::JwtKeys,
+        session::{revoke_session, start_session},
+        validation::ValidatedJson,
+    },
 };
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Validate)]
 pub struct RequestUser {
+    #[validate(length(min = 1, max = 64, message = "must be between 1 and 64 characters"))]
     username: String,
+    #[validate(length(min = 1, max = 128, message = "must be between 1 and 128 characters"))]
     password: String,
 }
 
@@ -21,38 +31,41 @@ pub struct ResponseUser {
     id: i32,
     username: String,
     token: String,
+    refresh_token: String,
 }
 
 pub async fn login(
     State(db_conn): State<DatabaseConnection>,
-    Json(req_user): Json<RequestUser>,
+    State(config): State<Arc<Config>>,
+    State(jwt_keys): State<Arc<JwtKeys>>,
+    user_agent: Option<TypedHeader<UserAgent>>,
+    ValidatedJson(req_user): ValidatedJson<RequestUser>,
 ) -> Result<Json<ResponseUser>, ApiError> {
     let db_search = Users::find()
         .filter(users::Column::Username.eq(&req_user.username))
+        .filter(users::Column::DeletedAt.is_null())
         .one(&db_conn)
-        .await
-        .map_err(|_err| ApiError::new(StatusCode::UNAUTHORIZED, "Invalid username"))?;
+        .await?;
 
     if let Some(user_model) = db_search {
         if !argon_verify(req_user.password, &user_model.password)? {
             return Err(ApiError::new(StatusCode::UNAUTHORIZED, "Invalid password"));
         }
 
-        let token = create_jwt()?;
-
-        let mut user = user_model.into_active_model();
-
-        user.token = Set(Some(token));
-
-        let save_user = user.save(&db_conn).await.map_err(|err| {
-            tracing::error!("Error: {:?}", err);
-            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal error")
-        })?;
+        let tokens = start_session(
+            &db_conn,
+            &config.jwt,
+            &jwt_keys,
+            &user_model,
+            user_agent.map(|TypedHeader(agent)| agent.to_string()),
+        )
+        .await?;
 
         Ok(Json(ResponseUser {
-            id: save_user.id.unwrap(),
-            username: save_user.username.unwrap(),
-            token: save_user.token.unwrap().unwrap(),
+            id: user_model.id,
+            username: user_model.username,
+            token: tokens.token,
+            refresh_token: tokens.refresh_token,
         }))
     } else {
         Err(ApiError::new(
@@ -62,18 +75,10 @@ pub async fn login(
     }
 }
 
+/// Ends the session the request was made with, other devices stay logged in
 pub async fn logout(
     State(db_conn): State<DatabaseConnection>,
-    Extension(user_model): Extension<Model>,
+    Extension(auth_user): Extension<AuthUser>,
 ) -> Result<(), ApiError> {
-    let mut user = user_model.into_active_model();
-
-    user.token = Set(None);
-
-    user.save(&db_conn).await.map_err(|err| {
-        tracing::error!("Error: {:?}", err);
-        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to update user")
-    })?;
-
-    Ok(())
+    revoke_session(&db_conn, auth_user.session_id).await
 }
@@ -0,0 +1,390 @@
+use axum::{
+    Extension, Json,
+    extract::State,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder, Set, SqlErr, TransactionTrait, prelude::Expr,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use validator::Validate;
+
+use super::ticket_get::{FilterTicketsParams, ResponseTicketPage, find_tickets};
+use crate::{
+    database::{
+        saved_views::{self, Entity as SavedViews},
+        users::{self, Entity as Users},
+    },
+    middleware::guard::AuthUser,
+    utils::{
+        api_error::{ApiError, FieldError},
+        extract::{Path, Query},
+        validation::{ValidatedJson, invalid_body, not_blank},
+    },
+};
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RequestView {
+    #[validate(
+        custom(function = "not_blank"),
+        length(min = 1, max = 64, message = "must be between 1 and 64 characters")
+    )]
+    name: String,
+    /// Same keys as the query parameters of `GET /tickets`, without paging
+    filters: Value,
+    #[serde(default)]
+    shared: bool,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RequestViewUpdate {
+    #[validate(
+        custom(function = "not_blank"),
+        length(min = 1, max = 64, message = "must be between 1 and 64 characters")
+    )]
+    name: Option<String>,
+    filters: Option<Value>,
+    shared: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RequestDefaultView {
+    /// `null` goes back to the plain ticket list
+    view_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct ViewTicketsParams {
+    limit: Option<u64>,
+    #[serde(default)]
+    offset: u64,
+}
+
+#[derive(Serialize)]
+pub struct ResponseView {
+    id: i32,
+    owner_id: i32,
+    name: String,
+    filters: Value,
+    shared: bool,
+    /// Whether this is the caller's landing view
+    default: bool,
+    created_at: DateTime<FixedOffset>,
+}
+
+impl ResponseView {
+    fn new(view: saved_views::Model, default_view_id: Option<i32>) -> Self {
+        Self {
+            default: default_view_id == Some(view.id),
+            id: view.id,
+            owner_id: view.user_id,
+            name: view.name,
+            filters: view.filters,
+            shared: view.shared,
+            created_at: view.created_at,
+        }
+    }
+}
+
+fn save_error(err: DbErr) -> ApiError {
+    match err.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => ApiError::new(
+            StatusCode::CONFLICT,
+            "You already have a view with this name",
+        )
+        .with_code("view_exists"),
+        _ => {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to save view")
+        }
+    }
+}
+
+/// Checks the filters the way `GET /tickets` would read them and stores
+/// them in that normalised form
+fn normalise_filters(filters: Value, user: &AuthUser) -> Result<Value, ApiError> {
+    let invalid = |reason: String| {
+        invalid_body(vec![FieldError {
+            field: "filters".to_owned(),
+            reason,
+        }])
+    };
+
+    if !filters.is_object() {
+        return Err(invalid("must be an object".to_owned()));
+    }
+
+    let params: FilterTicketsParams =
+        serde_json::from_value(filters).map_err(|err| invalid(err.to_string()))?;
+    params.check_query(user).map_err(|err| {
+        invalid_body(vec![FieldError {
+            field: "filters.q".to_owned(),
+            reason: format!("column {}: {}", err.column, err.reason),
+        }])
+    })?;
+
+    serde_json::to_value(params).map_err(|err| {
+        tracing::error!("Error: {:?}", err);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to save view")
+    })
+}
+
+/// Views the user owns, and the ones others share
+fn visible_to(user: &AuthUser) -> Condition {
+    Condition::any()
+        .add(saved_views::Column::UserId.eq(user.id))
+        .add(saved_views::Column::Shared.eq(true))
+}
+
+async fn default_view_id(
+    db_conn: &DatabaseConnection,
+    user: &AuthUser,
+) -> Result<Option<i32>, ApiError> {
+    let user = Users::find_by_id(user.id)
+        .one(db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch user")
+        })?;
+
+    Ok(user.and_then(|user| user.default_view_id))
+}
+
+/// A view the user may see, other users' private views are reported missing
+async fn find_view(
+    db_conn: &DatabaseConnection,
+    user: &AuthUser,
+    view_id: i32,
+) -> Result<saved_views::Model, ApiError> {
+    SavedViews::find_by_id(view_id)
+        .filter(visible_to(user))
+        .one(db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch view")
+        })?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No view found"))
+}
+
+/// A view the user may change, shared views stay read-only to everyone else
+async fn find_own_view(
+    db_conn: &DatabaseConnection,
+    user: &AuthUser,
+    view_id: i32,
+) -> Result<saved_views::Model, ApiError> {
+    let view = find_view(db_conn, user, view_id).await?;
+
+    if view.user_id != user.id {
+        return Err(
+            ApiError::new(StatusCode::FORBIDDEN, "Only the owner can change a view")
+                .with_code("not_view_owner"),
+        );
+    }
+
+    Ok(view)
+}
+
+pub async fn view_list(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Vec<ResponseView>>, ApiError> {
+    let default_view_id = default_view_id(&db_conn, &user).await?;
+
+    let views = SavedViews::find()
+        .filter(visible_to(&user))
+        .order_by_asc(saved_views::Column::Name)
+        .order_by_asc(saved_views::Column::Id)
+        .all(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error fetching views")
+        })?
+        .into_iter()
+        .map(|view| ResponseView::new(view, default_view_id))
+        .collect();
+
+    Ok(Json(views))
+}
+
+pub async fn view_get(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path(view_id): Path<i32>,
+) -> Result<Json<ResponseView>, ApiError> {
+    let view = find_view(&db_conn, &user, view_id).await?;
+    let default_view_id = default_view_id(&db_conn, &user).await?;
+
+    Ok(Json(ResponseView::new(view, default_view_id)))
+}
+
+pub async fn view_create(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    ValidatedJson(req_view): ValidatedJson<RequestView>,
+) -> Result<Response, ApiError> {
+    let filters = normalise_filters(req_view.filters, &user)?;
+
+    let view = saved_views::ActiveModel {
+        user_id: Set(user.id),
+        name: Set(req_view.name),
+        filters: Set(filters),
+        shared: Set(req_view.shared),
+        created_at: Set(Utc::now().fixed_offset()),
+        ..Default::default()
+    }
+    .insert(&db_conn)
+    .await
+    .map_err(save_error)?;
+
+    let default_view_id = default_view_id(&db_conn, &user).await?;
+
+    tracing::info!("View {:?} created by User {:?}", view.id, user.id);
+    let location = [(header::LOCATION, format!("/views/{}", view.id))];
+    Ok((
+        StatusCode::CREATED,
+        location,
+        Json(ResponseView::new(view, default_view_id)),
+    )
+        .into_response())
+}
+
+pub async fn view_update(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path(view_id): Path<i32>,
+    ValidatedJson(req_view): ValidatedJson<RequestViewUpdate>,
+) -> Result<Json<ResponseView>, ApiError> {
+    let view = find_own_view(&db_conn, &user, view_id).await?;
+    let unshared = view.shared && req_view.shared == Some(false);
+    let mut view = view.into_active_model();
+
+    if let Some(name) = req_view.name {
+        view.name = Set(name);
+    }
+
+    if let Some(filters) = req_view.filters {
+        view.filters = Set(normalise_filters(filters, &user)?);
+    }
+
+    if let Some(shared) = req_view.shared {
+        view.shared = Set(shared);
+    }
+
+    let txn = db_conn.begin().await?;
+
+    let view = view.update(&txn).await.map_err(save_error)?;
+
+    // Nobody else can open the view anymore, so it cannot stay their landing view
+    if unshared {
+        Users::update_many()
+            .col_expr(
+                users::Column::DefaultViewId,
+                Expr::value(Option::<i32>::None),
+            )
+            .filter(users::Column::DefaultViewId.eq(view.id))
+            .filter(users::Column::Id.ne(user.id))
+            .exec(&txn)
+            .await?;
+    }
+
+    txn.commit().await?;
+
+    let default_view_id = default_view_id(&db_conn, &user).await?;
+    Ok(Json(ResponseView::new(view, default_view_id)))
+}
+
+/// Users landing on the view go back to the plain ticket list
+pub async fn view_delete(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path(view_id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    let view = find_own_view(&db_conn, &user, view_id).await?;
+
+    SavedViews::delete_by_id(view.id)
+        .exec(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to delete view")
+        })?;
+
+    tracing::info!("View {:?} deleted by User {:?}", view.id, user.id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Runs the stored filters for the caller, with the caller's visibility
+/// and `me` standing for the caller in `q`
+pub async fn view_tickets(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    Path(view_id): Path<i32>,
+    Query(params): Query<ViewTicketsParams>,
+) -> Result<Json<ResponseTicketPage>, ApiError> {
+    let view = find_view(&db_conn, &user, view_id).await?;
+
+    let filters: FilterTicketsParams = serde_json::from_value(view.filters).map_err(|err| {
+        tracing::error!("Error: {:?}", err);
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Stored view filters are unreadable",
+        )
+    })?;
+
+    find_tickets(&db_conn, &user, filters.paged(params.limit, params.offset))
+        .await
+        .map(Json)
+}
+
+/// The view the caller lands on, if one is set and still visible to them
+pub async fn view_default_get(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<ResponseView>, ApiError> {
+    let not_set = || ApiError::new(StatusCode::NOT_FOUND, "No default view set");
+
+    let view_id = default_view_id(&db_conn, &user)
+        .await?
+        .ok_or_else(not_set)?;
+    let view = find_view(&db_conn, &user, view_id)
+        .await
+        .map_err(|_err| not_set())?;
+
+    Ok(Json(ResponseView::new(view, Some(view_id))))
+}
+
+/// Picks the landing view, any view the caller can see will do
+pub async fn view_default_set(
+    State(db_conn): State<DatabaseConnection>,
+    Extension(user): Extension<AuthUser>,
+    ValidatedJson(req_default): ValidatedJson<RequestDefaultView>,
+) -> Result<StatusCode, ApiError> {
+    if let Some(view_id) = req_default.view_id {
+        find_view(&db_conn, &user, view_id).await?;
+    }
+
+    Users::update_many()
+        .col_expr(
+            users::Column::DefaultViewId,
+            Expr::value(req_default.view_id),
+        )
+        .filter(users::Column::Id.eq(user.id))
+        .exec(&db_conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error: {:?}", err);
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to save default view",
+            )
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
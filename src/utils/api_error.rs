@@ -1,33 +1,161 @@
-use axum::{Json, http::StatusCode, response::IntoResponse};
+use axum::{
+    Json,
+    extract::rejection::{JsonRejection, PathRejection, QueryRejection},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use sea_orm::{DbErr, SqlErr};
 use serde::Serialize;
 
+use crate::middleware::request_context::RequestContext;
+
+/// Error answered as an RFC 7807 problem details document
 #[derive(Debug)]
 pub struct ApiError {
-    pub code: StatusCode,
-    pub message: String,
+    pub status: StatusCode,
+    /// Stable, machine-readable identifier clients can match on
+    pub code: &'static str,
+    pub detail: String,
+    pub errors: Vec<FieldError>,
+}
+
+/// Why a single request field was rejected
+#[derive(Serialize, Debug)]
+pub struct FieldError {
+    pub field: String,
+    pub reason: String,
 }
 
 #[derive(Serialize)]
-struct ResponseMessage {
-    message: String,
+struct ProblemDetails {
+    #[serde(rename = "type")]
+    problem_type: String,
+    title: &'static str,
+    status: u16,
+    detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instance: Option<String>,
+    code: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<FieldError>,
 }
 
 impl ApiError {
-    pub fn new(code: StatusCode, message: impl Into<String>) -> Self {
+    /// Error with the generic code for `status`, see `with_code` for specific ones
+    pub fn new(status: StatusCode, detail: impl Into<String>) -> Self {
         Self {
-            code,
-            message: message.into(),
+            status,
+            code: default_code(status),
+            detail: detail.into(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = code;
+        self
+    }
+
+    pub fn with_errors(mut self, errors: Vec<FieldError>) -> Self {
+        self.errors = errors;
+        self
+    }
+}
+
+fn default_code(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "bad_request",
+        StatusCode::UNAUTHORIZED => "unauthorized",
+        StatusCode::FORBIDDEN => "forbidden",
+        StatusCode::NOT_FOUND => "not_found",
+        StatusCode::METHOD_NOT_ALLOWED => "method_not_allowed",
+        StatusCode::CONFLICT => "conflict",
+        StatusCode::PRECONDITION_FAILED => "precondition_failed",
+        StatusCode::PAYLOAD_TOO_LARGE => "payload_too_large",
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => "unsupported_media_type",
+        StatusCode::UNPROCESSABLE_ENTITY => "validation_failed",
+        StatusCode::PRECONDITION_REQUIRED => "precondition_required",
+        StatusCode::SERVICE_UNAVAILABLE => "service_unavailable",
+        status if status.is_server_error() => "internal_error",
+        _ => "error",
+    }
+}
+
+impl From<DbErr> for ApiError {
+    fn from(err: DbErr) -> Self {
+        match err.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => {
+                ApiError::new(StatusCode::CONFLICT, "Resource already exists")
+                    .with_code("already_exists")
+            }
+            Some(SqlErr::ForeignKeyConstraintViolation(_)) => ApiError::new(
+                StatusCode::CONFLICT,
+                "Resource references a missing or protected record",
+            )
+            .with_code("constraint_violation"),
+            _ => match err {
+                DbErr::RecordNotFound(_) => {
+                    ApiError::new(StatusCode::NOT_FOUND, "Requested resource not found")
+                }
+                err => {
+                    tracing::error!("Error: {:?}", err);
+                    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+                        .with_code("database_error")
+                }
+            },
         }
     }
 }
 
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        let code = match rejection {
+            JsonRejection::JsonDataError(_) => "invalid_body",
+            JsonRejection::JsonSyntaxError(_) => "malformed_json",
+            JsonRejection::MissingJsonContentType(_) => "unsupported_media_type",
+            _ => "invalid_body",
+        };
+        ApiError::new(rejection.status(), rejection.body_text()).with_code(code)
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        let mut error = ApiError::new(rejection.status(), rejection.body_text());
+        if error.status.is_client_error() {
+            error = error.with_code("invalid_path");
+        }
+        error
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::new(rejection.status(), rejection.body_text()).with_code("invalid_query")
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
+        let context = RequestContext::current();
+
+        let problem = ProblemDetails {
+            problem_type: format!("urn:axum-ticketstore:problem:{}", self.code),
+            title: self.status.canonical_reason().unwrap_or("Error"),
+            status: self.status.as_u16(),
+            detail: self.detail,
+            instance: context.as_ref().map(|context| context.path.clone()),
+            code: self.code,
+            request_id: context.map(|context| context.request_id),
+            errors: self.errors,
+        };
+
         (
-            self.code,
-            Json(ResponseMessage {
-                message: self.message,
-            }),
+            self.status,
+            [(header::CONTENT_TYPE, "application/problem+json")],
+            Json(problem),
         )
             .into_response()
     }
@@ -0,0 +1,13 @@
+use axum::extract::FromRequestParts;
+
+use super::api_error::ApiError;
+
+/// `axum::extract::Path` answering a malformed path with problem details
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(ApiError))]
+pub struct Path<T>(pub T);
+
+/// `axum::extract::Query` answering a malformed query string with problem details
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(ApiError))]
+pub struct Query<T>(pub T);
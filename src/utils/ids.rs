@@ -0,0 +1,8 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+
+/// Random 128-bit identifier as 32 hex digits, e.g. for token ids and request ids
+pub fn random_id() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
@@ -1,52 +1,100 @@
 use axum::http::StatusCode;
 use chrono::{Duration, Utc};
-use dotenvy_macro::dotenv;
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use jsonwebtoken::{Header, Validation, decode, decode_header, encode};
 use serde::{Deserialize, Serialize};
 
-use super::api_error::ApiError;
+use super::{api_error::ApiError, jwt_keys::JwtKeys};
+use crate::{config::JwtConfig, database::sea_orm_active_enums::UserRole};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Claims {
-    iat: usize,
-    exp: usize,
+    /// User id
+    pub sub: String,
+    /// Session the token was issued for
+    pub sid: i32,
+    pub jti: String,
+    pub role: UserRole,
+    pub iss: String,
+    pub aud: String,
+    pub iat: usize,
+    pub exp: usize,
 }
 
-pub fn create_jwt() -> Result<String, ApiError> {
+impl Claims {
+    pub fn user_id(&self) -> Result<i32, ApiError> {
+        self.sub.parse().map_err(|_err| {
+            ApiError::new(StatusCode::UNAUTHORIZED, "Token denied, please log in")
+                .with_code("invalid_token")
+        })
+    }
+}
+
+pub fn create_jwt(
+    jwt_config: &JwtConfig,
+    jwt_keys: &JwtKeys,
+    user_id: i32,
+    role: UserRole,
+    session_id: i32,
+    jti: String,
+) -> Result<String, ApiError> {
     let mut now = Utc::now();
     let iat = now.timestamp() as usize;
-    let expiry = Duration::hours(2);
+    let expiry = Duration::minutes(jwt_config.expiry_minutes);
     now += expiry;
     let exp = now.timestamp() as usize;
 
-    let new_claim = Claims { iat, exp };
+    let new_claim = Claims {
+        sub: user_id.to_string(),
+        sid: session_id,
+        jti,
+        role,
+        iss: jwt_config.issuer.clone(),
+        aud: jwt_config.audience.clone(),
+        iat,
+        exp,
+    };
 
-    let secret: &'static str = dotenv!("JWT_SECRET");
-    let key = EncodingKey::from_secret(secret.as_bytes());
+    let mut header = Header::new(jwt_keys.signing_algorithm());
+    header.kid = Some(jwt_keys.signing_kid().to_owned());
 
-    encode(&Header::default(), &new_claim, &key)
+    encode(&header, &new_claim, jwt_keys.encoding_key())
         .map_err(|_err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Error generating token"))
 }
 
-pub fn is_valid(token: &str) -> Result<bool, ApiError> {
-    let secret: &'static str = dotenv!("JWT_SECRET");
-    let key = DecodingKey::from_secret(secret.as_bytes());
+/// Verifies signature, expiry, issuer and audience and returns the claims
+///
+/// The key is picked by the `kid` in the token header, and must use the
+/// algorithm configured for it
+pub fn is_valid(
+    token: &str,
+    jwt_config: &JwtConfig,
+    jwt_keys: &JwtKeys,
+) -> Result<Claims, ApiError> {
+    let denied = || {
+        ApiError::new(StatusCode::UNAUTHORIZED, "Token denied, please log in")
+            .with_code("invalid_token")
+    };
+
+    let header = decode_header(token).map_err(|_err| denied())?;
+    let (algorithm, key) = header
+        .kid
+        .as_deref()
+        .and_then(|kid| jwt_keys.verifying_key(kid))
+        .ok_or_else(denied)?;
+
+    let mut validation = Validation::new(algorithm);
+    validation.set_issuer(&[&jwt_config.issuer]);
+    validation.set_audience(&[&jwt_config.audience]);
+    validation.set_required_spec_claims(&["exp", "sub", "iss", "aud"]);
 
-    decode::<Claims>(
-        token,
-        &key,
-        &Validation::new(jsonwebtoken::Algorithm::HS256),
-    )
-    .map_err(|err| match err.kind() {
+    let token_data = decode::<Claims>(token, key, &validation).map_err(|err| match err.kind() {
         jsonwebtoken::errors::ErrorKind::ExpiredSignature => ApiError::new(
             StatusCode::UNAUTHORIZED,
             "Your session has expired, please log in",
-        ),
-        _ => ApiError::new(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Token denied, please log in",
-        ),
+        )
+        .with_code("token_expired"),
+        _ => denied(),
     })?;
 
-    Ok(true)
+    Ok(token_data.claims)
 }
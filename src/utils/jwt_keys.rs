@@ -0,0 +1,423 @@
+use std::{collections::HashMap, path::Path};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+};
+use rsa::{RsaPublicKey, pkcs8::DecodePublicKey, traits::PublicKeyParts};
+
+use crate::config::{ConfigError, JwtAlgorithm, JwtConfig, JwtKeyConfig};
+
+/// DER prefix of an Ed25519 `SubjectPublicKeyInfo`, the raw key makes up the last 32 bytes
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+struct VerifyingKey {
+    algorithm: Algorithm,
+    key: DecodingKey,
+    verify_until: Option<DateTime<Utc>>,
+    /// Public form of the key, `None` for shared secrets which must never be published
+    jwk: Option<Jwk>,
+}
+
+impl VerifyingKey {
+    fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.verify_until.is_none_or(|until| now < until)
+    }
+}
+
+/// Keys loaded from `JwtConfig` at startup
+///
+/// Tokens are signed with the single signing key and carry its `kid` in their
+/// header. Every configured key verifies tokens until its `verify_until`, so
+/// rotating means adding a new key, switching `signing_kid` to it and retiring
+/// the old one once the longest lived token signed with it has expired.
+pub struct JwtKeys {
+    signing_kid: String,
+    signing_algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    verifying_keys: HashMap<String, VerifyingKey>,
+}
+
+impl JwtKeys {
+    /// Expects a config that already passed `Config::load` validation
+    pub fn from_config(jwt_config: &JwtConfig) -> Result<Self, ConfigError> {
+        let signing_kid = jwt_config
+            .signing_kid()
+            .ok_or_else(|| ConfigError::Invalid {
+                field: "jwt.signing_kid",
+                reason: "no signing key configured".to_owned(),
+            })?;
+
+        let mut encoding = None;
+        let mut verifying_keys = HashMap::new();
+
+        for key_config in jwt_config.key_configs() {
+            if key_config.kid == signing_kid {
+                encoding = Some((
+                    algorithm(key_config.algorithm),
+                    load_encoding_key(&key_config)?,
+                ));
+            }
+
+            verifying_keys.insert(key_config.kid.clone(), load_verifying_key(&key_config)?);
+        }
+
+        let (signing_algorithm, encoding_key) = encoding.ok_or_else(|| ConfigError::Invalid {
+            field: "jwt.signing_kid",
+            reason: format!("no key with kid {signing_kid:?}"),
+        })?;
+
+        Ok(Self {
+            signing_kid,
+            signing_algorithm,
+            encoding_key,
+            verifying_keys,
+        })
+    }
+
+    pub fn signing_kid(&self) -> &str {
+        &self.signing_kid
+    }
+
+    pub fn signing_algorithm(&self) -> Algorithm {
+        self.signing_algorithm
+    }
+
+    pub fn encoding_key(&self) -> &EncodingKey {
+        &self.encoding_key
+    }
+
+    /// Key and algorithm for a token header's `kid`, if that key still verifies
+    pub fn verifying_key(&self, kid: &str) -> Option<(Algorithm, &DecodingKey)> {
+        self.verifying_keys
+            .get(kid)
+            .filter(|key| key.is_active(Utc::now()))
+            .map(|key| (key.algorithm, &key.key))
+    }
+
+    /// Public keys other services can verify our tokens with
+    pub fn jwks(&self) -> JwkSet {
+        let now = Utc::now();
+        let mut keys: Vec<Jwk> = self
+            .verifying_keys
+            .values()
+            .filter(|key| key.is_active(now))
+            .filter_map(|key| key.jwk.clone())
+            .collect();
+        keys.sort_by(|a, b| a.common.key_id.cmp(&b.common.key_id));
+
+        JwkSet { keys }
+    }
+}
+
+fn algorithm(algorithm: JwtAlgorithm) -> Algorithm {
+    match algorithm {
+        JwtAlgorithm::HS256 => Algorithm::HS256,
+        JwtAlgorithm::RS256 => Algorithm::RS256,
+        JwtAlgorithm::EdDSA => Algorithm::EdDSA,
+    }
+}
+
+fn key_error(key_config: &JwtKeyConfig, reason: impl Into<String>) -> ConfigError {
+    ConfigError::Key {
+        kid: key_config.kid.clone(),
+        reason: reason.into(),
+    }
+}
+
+fn read_pem(key_config: &JwtKeyConfig, path: Option<&Path>) -> Result<Vec<u8>, ConfigError> {
+    let path = path.ok_or_else(|| key_error(key_config, "missing key file path"))?;
+
+    std::fs::read(path).map_err(|source| ConfigError::Read {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn load_encoding_key(key_config: &JwtKeyConfig) -> Result<EncodingKey, ConfigError> {
+    let key = match key_config.algorithm {
+        JwtAlgorithm::HS256 => {
+            let secret = key_config.secret.as_deref().unwrap_or_default();
+            return Ok(EncodingKey::from_secret(secret.as_bytes()));
+        }
+        JwtAlgorithm::RS256 => EncodingKey::from_rsa_pem(&read_pem(
+            key_config,
+            key_config.private_key_path.as_deref(),
+        )?),
+        JwtAlgorithm::EdDSA => EncodingKey::from_ed_pem(&read_pem(
+            key_config,
+            key_config.private_key_path.as_deref(),
+        )?),
+    };
+
+    key.map_err(|err| key_error(key_config, format!("unreadable private key: {err}")))
+}
+
+fn load_verifying_key(key_config: &JwtKeyConfig) -> Result<VerifyingKey, ConfigError> {
+    let common = CommonParameters {
+        public_key_use: Some(PublicKeyUse::Signature),
+        key_id: Some(key_config.kid.clone()),
+        ..Default::default()
+    };
+
+    let (key, jwk) = match key_config.algorithm {
+        JwtAlgorithm::HS256 => {
+            let secret = key_config.secret.as_deref().unwrap_or_default();
+            (DecodingKey::from_secret(secret.as_bytes()), None)
+        }
+        JwtAlgorithm::RS256 => {
+            let pem = read_pem(key_config, key_config.public_key_path.as_deref())?;
+            let pem = String::from_utf8_lossy(&pem);
+            let public_key = RsaPublicKey::from_public_key_pem(&pem)
+                .map_err(|err| key_error(key_config, format!("unreadable public key: {err}")))?;
+
+            let modulus = public_key.n().to_bytes_be();
+            let exponent = public_key.e().to_bytes_be();
+
+            let jwk = Jwk {
+                common: CommonParameters {
+                    key_algorithm: Some(KeyAlgorithm::RS256),
+                    ..common
+                },
+                algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n: URL_SAFE_NO_PAD.encode(&modulus),
+                    e: URL_SAFE_NO_PAD.encode(&exponent),
+                }),
+            };
+
+            (
+                DecodingKey::from_rsa_raw_components(&modulus, &exponent),
+                Some(jwk),
+            )
+        }
+        JwtAlgorithm::EdDSA => {
+            let pem = read_pem(key_config, key_config.public_key_path.as_deref())?;
+            let der = pem::parse(&pem)
+                .map_err(|err| key_error(key_config, format!("unreadable public key: {err}")))?;
+            let raw_key = der
+                .contents()
+                .strip_prefix(ED25519_SPKI_PREFIX.as_slice())
+                .filter(|raw_key| raw_key.len() == 32)
+                .ok_or_else(|| key_error(key_config, "public key is not an Ed25519 key"))?;
+
+            let jwk = Jwk {
+                common: CommonParameters {
+                    key_algorithm: Some(KeyAlgorithm::EdDSA),
+                    ..common
+                },
+                algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(raw_key),
+                }),
+            };
+
+            (DecodingKey::from_ed_der(raw_key), Some(jwk))
+        }
+    };
+
+    Ok(VerifyingKey {
+        algorithm: algorithm(key_config.algorithm),
+        key,
+        verify_until: key_config.verify_until,
+        jwk,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use jsonwebtoken::{Header, Validation};
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    const SECRET: &str = "0123456789abcdef";
+
+    fn fixture(name: &str) -> Option<std::path::PathBuf> {
+        Some(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/jwt")
+                .join(name),
+        )
+    }
+
+    fn hs256(kid: &str) -> JwtKeyConfig {
+        JwtKeyConfig {
+            kid: kid.to_owned(),
+            algorithm: JwtAlgorithm::HS256,
+            secret: Some(SECRET.to_owned()),
+            private_key_path: None,
+            public_key_path: None,
+            verify_until: None,
+        }
+    }
+
+    fn rs256(kid: &str) -> JwtKeyConfig {
+        JwtKeyConfig {
+            kid: kid.to_owned(),
+            algorithm: JwtAlgorithm::RS256,
+            secret: None,
+            private_key_path: fixture("rsa_private.pem"),
+            public_key_path: fixture("rsa_public.pem"),
+            verify_until: None,
+        }
+    }
+
+    fn ed25519(kid: &str) -> JwtKeyConfig {
+        JwtKeyConfig {
+            kid: kid.to_owned(),
+            algorithm: JwtAlgorithm::EdDSA,
+            secret: None,
+            private_key_path: fixture("ed25519_private.pem"),
+            public_key_path: fixture("ed25519_public.pem"),
+            verify_until: None,
+        }
+    }
+
+    fn keys(signing_kid: &str, keys: Vec<JwtKeyConfig>) -> JwtKeys {
+        JwtKeys::from_config(&JwtConfig {
+            signing_kid: Some(signing_kid.to_owned()),
+            keys,
+            ..JwtConfig::default()
+        })
+        .expect("keys load")
+    }
+
+    fn published_kids(keys: &JwtKeys) -> Vec<String> {
+        keys.jwks()
+            .keys
+            .into_iter()
+            .filter_map(|jwk| jwk.common.key_id)
+            .collect()
+    }
+
+    /// Signs a token with the signing key and verifies it with the key its
+    /// header names
+    fn round_trip(keys: &JwtKeys) -> Value {
+        let header = Header {
+            kid: Some(keys.signing_kid().to_owned()),
+            ..Header::new(keys.signing_algorithm())
+        };
+        let claims = json!({"sub": "1", "exp": (Utc::now() + Duration::minutes(5)).timestamp()});
+        let token = jsonwebtoken::encode(&header, &claims, keys.encoding_key()).unwrap();
+
+        let kid = jsonwebtoken::decode_header(&token).unwrap().kid.unwrap();
+        let (algorithm, key) = keys.verifying_key(&kid).expect("kid is known");
+        jsonwebtoken::decode::<Value>(&token, key, &Validation::new(algorithm))
+            .unwrap()
+            .claims
+    }
+
+    #[test]
+    fn secret_shorthand_signs_and_verifies() {
+        let keys = JwtKeys::from_config(&JwtConfig {
+            secret: SECRET.to_owned(),
+            ..JwtConfig::default()
+        })
+        .unwrap();
+
+        assert_eq!(keys.signing_kid(), "default");
+        assert_eq!(keys.signing_algorithm(), Algorithm::HS256);
+        assert_eq!(round_trip(&keys)["sub"], "1");
+    }
+
+    #[test]
+    fn asymmetric_keys_sign_and_verify() {
+        let rsa = keys("rsa", vec![rs256("rsa")]);
+        assert_eq!(rsa.signing_algorithm(), Algorithm::RS256);
+        assert_eq!(round_trip(&rsa)["sub"], "1");
+
+        let ed = keys("ed", vec![ed25519("ed")]);
+        assert_eq!(ed.signing_algorithm(), Algorithm::EdDSA);
+        assert_eq!(round_trip(&ed)["sub"], "1");
+    }
+
+    #[test]
+    fn verifying_key_is_chosen_by_kid() {
+        let keys = keys("ed", vec![hs256("hs"), rs256("rsa"), ed25519("ed")]);
+
+        assert_eq!(keys.signing_kid(), "ed");
+        assert_eq!(keys.verifying_key("hs").unwrap().0, Algorithm::HS256);
+        assert_eq!(keys.verifying_key("rsa").unwrap().0, Algorithm::RS256);
+        assert_eq!(keys.verifying_key("ed").unwrap().0, Algorithm::EdDSA);
+        assert!(keys.verifying_key("unknown").is_none());
+    }
+
+    #[test]
+    fn retired_keys_verify_until_their_grace_window_ends() {
+        let mut retiring = rs256("retiring");
+        retiring.verify_until = Some(Utc::now() + Duration::hours(1));
+        let mut retired = ed25519("retired");
+        retired.verify_until = Some(Utc::now() - Duration::seconds(1));
+
+        let keys = keys("hs", vec![hs256("hs"), retiring, retired]);
+
+        assert!(keys.verifying_key("retiring").is_some());
+        assert!(keys.verifying_key("retired").is_none());
+        assert_eq!(published_kids(&keys), ["retiring"]);
+    }
+
+    #[test]
+    fn jwks_publishes_public_keys_only() {
+        let keys = keys("hs", vec![hs256("hs"), rs256("rsa"), ed25519("ed")]);
+
+        assert_eq!(published_kids(&keys), ["ed", "rsa"]);
+
+        let jwks = serde_json::to_value(keys.jwks()).unwrap();
+        let published = jwks.to_string();
+        assert!(!published.contains("\"oct\""), "{published}");
+        assert!(!published.contains("\"hs\""), "{published}");
+        assert!(!published.contains(SECRET), "{published}");
+        for jwk in jwks["keys"].as_array().unwrap() {
+            assert!(jwk.get("d").is_none(), "private part published: {jwk}");
+            assert_eq!(jwk["use"], "sig");
+        }
+    }
+
+    #[test]
+    fn hs256_keys_are_never_published() {
+        let keys = keys("new", vec![hs256("old"), hs256("new")]);
+
+        assert!(keys.jwks().keys.is_empty());
+    }
+
+    #[test]
+    fn missing_key_files_are_reported() {
+        let mut key = rs256("rsa");
+        key.public_key_path = fixture("missing.pem");
+
+        let err = JwtKeys::from_config(&JwtConfig {
+            keys: vec![key],
+            ..JwtConfig::default()
+        })
+        .err()
+        .expect("missing file is rejected");
+        assert!(matches!(err, ConfigError::Read { .. }), "{err}");
+    }
+
+    #[test]
+    fn wrong_key_type_is_reported() {
+        let mut key = ed25519("ed");
+        key.public_key_path = fixture("rsa_public.pem");
+
+        let err = JwtKeys::from_config(&JwtConfig {
+            keys: vec![key],
+            ..JwtConfig::default()
+        })
+        .err()
+        .expect("RSA key is rejected as Ed25519");
+        assert_eq!(
+            err.to_string(),
+            "invalid jwt key \"ed\": public key is not an Ed25519 key"
+        );
+    }
+}
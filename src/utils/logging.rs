@@ -1,10 +1,14 @@
 use tracing_subscriber::{EnvFilter, fmt};
 
-pub fn init_tracing() {
-    let subscriber = fmt::Subscriber::builder()
-        .with_env_filter(EnvFilter::from_default_env())
-        .finish();
+use crate::config::{LogConfig, LogFormat};
 
-    tracing::subscriber::set_global_default(subscriber)
-        .expect("Failed to initialize tracing subscriber")
+pub fn init_tracing(log_config: &LogConfig) {
+    let builder = fmt::Subscriber::builder().with_env_filter(EnvFilter::from_default_env());
+
+    let result = match log_config.format {
+        LogFormat::Pretty => tracing::subscriber::set_global_default(builder.finish()),
+        LogFormat::Json => tracing::subscriber::set_global_default(builder.json().finish()),
+    };
+
+    result.expect("Failed to initialize tracing subscriber")
 }
@@ -1,4 +1,11 @@
 pub mod api_error;
+pub mod extract;
 pub mod hashing;
+pub mod ids;
 pub mod jwt;
+pub mod jwt_keys;
 pub mod logging;
+pub mod precondition;
+pub mod prefer;
+pub mod session;
+pub mod validation;
@@ -0,0 +1,162 @@
+use axum::http::{HeaderMap, StatusCode};
+use axum_extra::headers::{ETag, HeaderMapExt, IfMatch, IfNoneMatch};
+
+use super::api_error::ApiError;
+
+/// Entity tag of a ticket, it changes with every write to the ticket
+pub fn ticket_etag(version: i32) -> ETag {
+    format!("\"{version}\"")
+        .parse()
+        .expect("a quoted number is a valid entity tag")
+}
+
+/// A write lost the race against another one, or `If-Match` named an old version
+pub fn ticket_changed() -> ApiError {
+    ApiError::new(
+        StatusCode::PRECONDITION_FAILED,
+        "Ticket was changed by someone else, fetch it again and retry",
+    )
+}
+
+/// Checks `If-Match` against the current entity tag of the resource
+///
+/// Without the header the write goes ahead, unless `required`, in which case
+/// it is answered with 428
+pub fn check_if_match(headers: &HeaderMap, etag: &ETag, required: bool) -> Result<(), ApiError> {
+    let if_match = headers.typed_try_get::<IfMatch>().map_err(|_err| {
+        ApiError::new(StatusCode::BAD_REQUEST, "Malformed If-Match header")
+            .with_code("invalid_precondition")
+    })?;
+
+    match if_match {
+        Some(if_match) if !if_match.precondition_passes(etag) => Err(ticket_changed()),
+        Some(_) => Ok(()),
+        None if required => Err(ApiError::new(
+            StatusCode::PRECONDITION_REQUIRED,
+            "Send the ticket's ETag in If-Match to change it",
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Whether `If-None-Match` names the current entity tag, i.e. the client's
+/// copy is still fresh and a 304 can be sent instead of the body
+pub fn is_not_modified(headers: &HeaderMap, etag: &ETag) -> Result<bool, ApiError> {
+    let if_none_match = headers.typed_try_get::<IfNoneMatch>().map_err(|_err| {
+        ApiError::new(StatusCode::BAD_REQUEST, "Malformed If-None-Match header")
+            .with_code("invalid_precondition")
+    })?;
+
+    Ok(if_none_match.is_some_and(|if_none_match| !if_none_match.precondition_passes(etag)))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{HeaderValue, header};
+
+    use super::*;
+
+    fn with(name: header::HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    fn if_match(value: &str) -> HeaderMap {
+        with(header::IF_MATCH, value)
+    }
+
+    fn if_none_match(value: &str) -> HeaderMap {
+        with(header::IF_NONE_MATCH, value)
+    }
+
+    fn status(result: Result<(), ApiError>) -> Option<StatusCode> {
+        result.err().map(|err| err.status)
+    }
+
+    #[test]
+    fn etag_is_the_quoted_version() {
+        assert_eq!(ticket_etag(7), "\"7\"".parse::<ETag>().unwrap());
+    }
+
+    #[test]
+    fn current_etag_passes() {
+        let etag = ticket_etag(3);
+
+        assert_eq!(
+            status(check_if_match(&if_match("\"3\""), &etag, true)),
+            None
+        );
+        assert_eq!(
+            status(check_if_match(&if_match("\"1\", \"3\""), &etag, true)),
+            None
+        );
+    }
+
+    #[test]
+    fn any_etag_passes() {
+        assert_eq!(
+            status(check_if_match(&if_match("*"), &ticket_etag(3), true)),
+            None
+        );
+    }
+
+    #[test]
+    fn stale_etag_fails() {
+        let err = check_if_match(&if_match("\"2\""), &ticket_etag(3), false).unwrap_err();
+
+        assert_eq!(err.status, StatusCode::PRECONDITION_FAILED);
+        assert_eq!(err.code, ticket_changed().code);
+    }
+
+    #[test]
+    fn weak_etag_never_matches() {
+        // If-Match compares strongly, a weak tag does not prove the client
+        // saw these exact bytes
+        assert_eq!(
+            status(check_if_match(&if_match("W/\"3\""), &ticket_etag(3), false)),
+            Some(StatusCode::PRECONDITION_FAILED)
+        );
+    }
+
+    #[test]
+    fn missing_if_match_only_fails_when_required() {
+        let etag = ticket_etag(3);
+
+        assert_eq!(
+            status(check_if_match(&HeaderMap::new(), &etag, false)),
+            None
+        );
+        assert_eq!(
+            status(check_if_match(&HeaderMap::new(), &etag, true)),
+            Some(StatusCode::PRECONDITION_REQUIRED)
+        );
+    }
+
+    #[test]
+    fn unquoted_etag_never_matches() {
+        assert_eq!(
+            status(check_if_match(&if_match("3"), &ticket_etag(3), false)),
+            Some(StatusCode::PRECONDITION_FAILED)
+        );
+    }
+
+    #[test]
+    fn if_none_match_with_the_current_etag_is_not_modified() {
+        let etag = ticket_etag(3);
+
+        assert!(is_not_modified(&if_none_match("\"3\""), &etag).unwrap());
+        assert!(is_not_modified(&if_none_match("W/\"3\""), &etag).unwrap());
+        assert!(is_not_modified(&if_none_match("\"1\", \"3\""), &etag).unwrap());
+        assert!(is_not_modified(&if_none_match("*"), &etag).unwrap());
+    }
+
+    #[test]
+    fn if_none_match_with_another_etag_is_modified() {
+        let etag = ticket_etag(3);
+
+        assert!(!is_not_modified(&HeaderMap::new(), &etag).unwrap());
+        assert!(!is_not_modified(&if_none_match("\"2\""), &etag).unwrap());
+        assert!(!is_not_modified(&if_none_match("3"), &etag).unwrap());
+    }
+}
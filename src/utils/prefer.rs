@@ -0,0 +1,147 @@
+use axum::{
+    Json,
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, IntoResponseParts, Response},
+};
+use serde::Serialize;
+
+pub static PREFER_HEADER: HeaderName = HeaderName::from_static("prefer");
+pub static PREFERENCE_APPLIED_HEADER: HeaderName = HeaderName::from_static("preference-applied");
+
+/// Whether the client sent `Prefer: return=minimal` (RFC 7240) and does not
+/// want the written resource echoed back
+pub fn wants_minimal(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(&PREFER_HEADER)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|preference| preference.split(';').next())
+        .filter_map(|preference| preference.split_once('='))
+        .any(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("return")
+                && value
+                    .trim()
+                    .trim_matches('"')
+                    .eq_ignore_ascii_case("minimal")
+        })
+}
+
+/// Answers a write with the resource as it now stands
+///
+/// With `return=minimal` only the status and headers are sent, a 200 becomes
+/// a 204 since there is no body left to describe.
+pub fn written<T: Serialize>(
+    headers: &HeaderMap,
+    status: StatusCode,
+    parts: impl IntoResponseParts,
+    resource: T,
+) -> Response {
+    if wants_minimal(headers) {
+        let status = match status {
+            StatusCode::OK => StatusCode::NO_CONTENT,
+            status => status,
+        };
+        let applied = [(
+            PREFERENCE_APPLIED_HEADER.clone(),
+            HeaderValue::from_static("return=minimal"),
+        )];
+
+        (status, applied, parts, ()).into_response()
+    } else {
+        (status, parts, Json(resource)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body, http::header};
+    use serde_json::json;
+
+    use super::*;
+
+    fn prefer(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(&PREFER_HEADER, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn return_minimal_is_recognised() {
+        assert!(wants_minimal(&prefer(&["return=minimal"])));
+        assert!(wants_minimal(&prefer(&["return=\"minimal\""])));
+        assert!(wants_minimal(&prefer(&["Return = MINIMAL"])));
+    }
+
+    #[test]
+    fn return_minimal_among_other_preferences() {
+        assert!(wants_minimal(&prefer(&["respond-async, return=minimal"])));
+        assert!(wants_minimal(&prefer(&[
+            "return=minimal; foo=bar, wait=10"
+        ])));
+        assert!(wants_minimal(&prefer(&["respond-async", "return=minimal"])));
+    }
+
+    #[test]
+    fn other_preferences_are_not_minimal() {
+        assert!(!wants_minimal(&HeaderMap::new()));
+        assert!(!wants_minimal(&prefer(&["return=representation"])));
+        assert!(!wants_minimal(&prefer(&["respond-async, wait=10"])));
+        assert!(!wants_minimal(&prefer(&["handling=minimal"])));
+        assert!(!wants_minimal(&prefer(&["return"])));
+    }
+
+    #[tokio::test]
+    async fn written_echoes_the_resource_by_default() {
+        let response = written(
+            &HeaderMap::new(),
+            StatusCode::OK,
+            [(header::ETAG, "\"2\"")],
+            json!({"id": 1}),
+        );
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ETAG], "\"2\"");
+        assert!(!response.headers().contains_key(&PREFERENCE_APPLIED_HEADER));
+        let body = body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], br#"{"id":1}"#);
+    }
+
+    #[tokio::test]
+    async fn written_with_return_minimal_drops_the_body() {
+        let response = written(
+            &prefer(&["return=minimal"]),
+            StatusCode::OK,
+            [(header::ETAG, "\"2\"")],
+            json!({"id": 1}),
+        );
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(response.headers()[header::ETAG], "\"2\"");
+        assert_eq!(
+            response.headers()[&PREFERENCE_APPLIED_HEADER],
+            "return=minimal"
+        );
+        let body = body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[test]
+    fn written_with_return_minimal_keeps_created() {
+        let response = written(
+            &prefer(&["return=minimal"]),
+            StatusCode::CREATED,
+            [(header::LOCATION, "/tickets/1")],
+            json!({"id": 1}),
+        );
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()[header::LOCATION], "/tickets/1");
+    }
+}
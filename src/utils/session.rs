@@ -0,0 +1,225 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use axum::http::StatusCode;
+use chrono::{Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+    TransactionTrait, prelude::Expr,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::{api_error::ApiError, ids::random_id, jwt::create_jwt, jwt_keys::JwtKeys};
+use crate::{
+    config::JwtConfig,
+    database::{
+        refresh_tokens::{self, Entity as RefreshTokens},
+        sessions::{self, Entity as Sessions},
+        users::{self, Entity as Users},
+    },
+};
+
+#[derive(Serialize, Debug)]
+pub struct TokenPair {
+    pub token: String,
+    pub refresh_token: String,
+}
+
+/// Opaque 256-bit refresh token, only its hash is ever stored
+fn generate_refresh_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    to_hex(&bytes)
+}
+
+/// Refresh tokens are high entropy, so a fast hash is enough and keeps lookups indexable
+pub fn hash_refresh_token(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn db_error(err: sea_orm::DbErr) -> ApiError {
+    tracing::error!("Error: {:?}", err);
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal error")
+}
+
+/// Opens a new session for a freshly authenticated user
+pub async fn start_session(
+    db_conn: &DatabaseConnection,
+    jwt_config: &JwtConfig,
+    jwt_keys: &JwtKeys,
+    user: &users::Model,
+    user_agent: Option<String>,
+) -> Result<TokenPair, ApiError> {
+    let now = Utc::now().fixed_offset();
+    let jti = random_id();
+    let refresh_token = generate_refresh_token();
+
+    let txn = db_conn.begin().await.map_err(db_error)?;
+
+    let session = sessions::ActiveModel {
+        user_id: Set(user.id),
+        access_jti: Set(Some(jti.clone())),
+        user_agent: Set(user_agent),
+        created_at: Set(now),
+        last_used_at: Set(now),
+        expires_at: Set(now + Duration::days(jwt_config.refresh_expiry_days)),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(db_error)?;
+
+    refresh_tokens::ActiveModel {
+        session_id: Set(session.id),
+        token_hash: Set(hash_refresh_token(&refresh_token)),
+        created_at: Set(now),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(db_error)?;
+
+    let token = create_jwt(jwt_config, jwt_keys, user.id, user.role, session.id, jti)?;
+
+    txn.commit().await.map_err(db_error)?;
+
+    Ok(TokenPair {
+        token,
+        refresh_token,
+    })
+}
+
+/// Marks a session as ended, its access and refresh tokens stop working
+pub async fn revoke_session(db_conn: &DatabaseConnection, session_id: i32) -> Result<(), ApiError> {
+    Sessions::update_many()
+        .col_expr(
+            sessions::Column::RevokedAt,
+            Expr::value(Utc::now().fixed_offset()),
+        )
+        .col_expr(
+            sessions::Column::AccessJti,
+            Expr::value(Option::<String>::None),
+        )
+        .filter(sessions::Column::Id.eq(session_id))
+        .filter(sessions::Column::RevokedAt.is_null())
+        .exec(db_conn)
+        .await
+        .map_err(db_error)?;
+
+    Ok(())
+}
+
+/// Exchanges a refresh token for a new token pair
+///
+/// Each refresh token is single use. Presenting one that was already
+/// exchanged means it leaked, so the whole session is revoked.
+pub async fn rotate_refresh_token(
+    db_conn: &DatabaseConnection,
+    jwt_config: &JwtConfig,
+    jwt_keys: &JwtKeys,
+    refresh_token: &str,
+) -> Result<TokenPair, ApiError> {
+    let unauthorized = || {
+        ApiError::new(StatusCode::UNAUTHORIZED, "Invalid refresh token")
+            .with_code("invalid_refresh_token")
+    };
+    let now = Utc::now().fixed_offset();
+
+    let stored_token = RefreshTokens::find()
+        .filter(refresh_tokens::Column::TokenHash.eq(hash_refresh_token(refresh_token)))
+        .one(db_conn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(unauthorized)?;
+
+    let session = Sessions::find_by_id(stored_token.session_id)
+        .one(db_conn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(unauthorized)?;
+
+    if session.revoked_at.is_some() || session.expires_at < now {
+        return Err(ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "Your session has expired, please log in",
+        )
+        .with_code("session_expired"));
+    }
+
+    let txn = db_conn.begin().await.map_err(db_error)?;
+
+    // Claiming the token with a conditional update makes concurrent refreshes
+    // with the same token count as reuse too
+    let claimed = RefreshTokens::update_many()
+        .col_expr(refresh_tokens::Column::UsedAt, Expr::value(now))
+        .filter(refresh_tokens::Column::Id.eq(stored_token.id))
+        .filter(refresh_tokens::Column::UsedAt.is_null())
+        .exec(&txn)
+        .await
+        .map_err(db_error)?;
+
+    if claimed.rows_affected == 0 {
+        txn.rollback().await.map_err(db_error)?;
+        tracing::warn!(
+            "Refresh token reuse detected, revoking session {:?}",
+            session.id
+        );
+        revoke_session(db_conn, session.id).await?;
+        return Err(ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "Refresh token already used, session revoked, please log in",
+        )
+        .with_code("refresh_token_reused"));
+    }
+
+    // Re-read the user so role changes show up in the new access token, and
+    // removed accounts cannot keep their sessions alive
+    let user = Users::find_by_id(session.user_id)
+        .filter(users::Column::DeletedAt.is_null())
+        .one(&txn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(unauthorized)?;
+
+    let jti = random_id();
+    let token = create_jwt(
+        jwt_config,
+        jwt_keys,
+        user.id,
+        user.role,
+        session.id,
+        jti.clone(),
+    )?;
+    let new_refresh_token = generate_refresh_token();
+
+    refresh_tokens::ActiveModel {
+        session_id: Set(session.id),
+        token_hash: Set(hash_refresh_token(&new_refresh_token)),
+        created_at: Set(now),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(db_error)?;
+
+    sessions::ActiveModel {
+        id: Set(session.id),
+        access_jti: Set(Some(jti)),
+        last_used_at: Set(now),
+        expires_at: Set(now + Duration::days(jwt_config.refresh_expiry_days)),
+        ..Default::default()
+    }
+    .update(&txn)
+    .await
+    .map_err(db_error)?;
+
+    txn.commit().await.map_err(db_error)?;
+
+    Ok(TokenPair {
+        token,
+        refresh_token: new_refresh_token,
+    })
+}
@@ -0,0 +1,103 @@
+use std::sync::LazyLock;
+
+use axum::{
+    Json,
+    extract::{FromRequest, Request},
+    http::StatusCode,
+};
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use super::api_error::{ApiError, FieldError};
+
+/// Letters, digits, `_`, `.` and `-`
+pub static USERNAME_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z0-9_.-]+$").unwrap());
+
+/// Letters, digits, spaces, `_`, `.`, `:`, `/` and `-`, no commas so names can be listed in a query
+pub static LABEL_NAME_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z0-9 _.:/-]+$").unwrap());
+
+/// Uppercase letter followed by uppercase letters or digits, e.g. `OPS`
+pub static PROJECT_KEY_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Z][A-Z0-9]*$").unwrap());
+
+/// `#rrggbb` hex colour
+pub static COLOUR_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^#[0-9A-Fa-f]{6}$").unwrap());
+
+/// JSON body extractor that also runs the body's `Validate` rules
+///
+/// Failing rules are answered with a 422 listing every offending field
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(request, state).await?;
+
+        value.validate().map_err(|errors| {
+            let mut field_errors = Vec::new();
+            collect_field_errors(&errors, None, &mut field_errors);
+            invalid_body(field_errors)
+        })?;
+
+        Ok(Self(value))
+    }
+}
+
+/// 422 listing every offending field
+pub fn invalid_body(mut field_errors: Vec<FieldError>) -> ApiError {
+    // HashMap order is random, keep responses stable
+    field_errors.sort_by(|a, b| a.field.cmp(&b.field));
+    ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, "Invalid request body")
+        .with_errors(field_errors)
+}
+
+/// Flattens nested validation errors into `parent.child` / `list[0].child` paths
+pub fn collect_field_errors(
+    errors: &ValidationErrors,
+    prefix: Option<&str>,
+    out: &mut Vec<FieldError>,
+) {
+    for (field, kind) in errors.errors() {
+        let path = match prefix {
+            Some(prefix) => format!("{prefix}.{field}"),
+            None => field.to_string(),
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(field_errors) => {
+                out.extend(field_errors.iter().map(|error| {
+                    FieldError {
+                        field: path.clone(),
+                        reason: error
+                            .message
+                            .as_ref()
+                            .map_or_else(|| error.code.to_string(), |message| message.to_string()),
+                    }
+                }));
+            }
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(errors, Some(&path), out),
+            ValidationErrorsKind::List(list) => {
+                for (index, errors) in list {
+                    collect_field_errors(errors, Some(&format!("{path}[{index}]")), out);
+                }
+            }
+        }
+    }
+}
+
+/// Rejects strings made only of whitespace
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(ValidationError::new("not_blank").with_message("must not be blank".into()));
+    }
+    Ok(())
+}